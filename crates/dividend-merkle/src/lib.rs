@@ -0,0 +1,283 @@
+//! Merkle trees for property dividend distributions.
+//!
+//! `property_dividends` commits to off-chain holder lists with a merkle root and
+//! verifies claims against it. This crate defines the leaf encodings shared by the
+//! program and the off-chain tooling, and builds trees and proofs from holder lists.
+//!
+//! Internal nodes hash their two children in sorted order, so a proof is just the
+//! list of sibling hashes from the leaf up to the root. A node without a sibling is
+//! carried up to the next layer unchanged.
+
+use solana_program::keccak;
+use solana_program::pubkey::Pubkey;
+
+const BALANCE_LEAF_TAG: u8 = 0;
+const NODE_TAG: u8 = 1;
+const AMOUNT_LEAF_TAG: u8 = 2;
+
+/// Leaf committing to a holder's property token balance at a snapshot slot
+pub fn balance_leaf(holder: &Pubkey, balance: u64) -> [u8; 32] {
+    keccak::hashv(&[&[BALANCE_LEAF_TAG], holder.as_ref(), &balance.to_le_bytes()]).to_bytes()
+}
+
+/// Leaf committing to a holder's dividend amount; `index` is the holder's slot in
+/// the distribution's claimed bitmap
+pub fn amount_leaf(index: u32, holder: &Pubkey, amount: u64) -> [u8; 32] {
+    keccak::hashv(&[
+        &[AMOUNT_LEAF_TAG],
+        &index.to_le_bytes(),
+        holder.as_ref(),
+        &amount.to_le_bytes(),
+    ])
+    .to_bytes()
+}
+
+/// Parent of two nodes
+pub fn hash_pair(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (left, right) = if a <= b { (a, b) } else { (b, a) };
+    keccak::hashv(&[&[NODE_TAG], left, right]).to_bytes()
+}
+
+/// Check that `leaf` is included in the tree with the given `root`
+pub fn verify_proof(root: &[u8; 32], leaf: [u8; 32], proof: &[[u8; 32]]) -> bool {
+    let computed = proof.iter().fold(leaf, |node, sibling| hash_pair(&node, sibling));
+    computed == *root
+}
+
+/// Merkle tree over precomputed leaves
+#[derive(Clone, Debug)]
+pub struct MerkleTree {
+    layers: Vec<Vec<[u8; 32]>>,
+}
+
+impl MerkleTree {
+    /// Build a tree from its leaves, in order. Panics if `leaves` is empty.
+    pub fn new(leaves: Vec<[u8; 32]>) -> Self {
+        assert!(!leaves.is_empty(), "merkle tree needs at least one leaf");
+
+        let mut layers = vec![leaves];
+        while layers.last().map_or(0, Vec::len) > 1 {
+            let next = layers
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => hash_pair(left, right),
+                    [single] => *single,
+                    _ => unreachable!(),
+                })
+                .collect();
+            layers.push(next);
+        }
+
+        Self { layers }
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        self.layers.last().unwrap()[0]
+    }
+
+    pub fn leaf_count(&self) -> usize {
+        self.layers[0].len()
+    }
+
+    pub fn leaf(&self, index: usize) -> [u8; 32] {
+        self.layers[0][index]
+    }
+
+    /// Sibling hashes from the leaf at `index` up to the root
+    pub fn proof(&self, index: usize) -> Vec<[u8; 32]> {
+        assert!(index < self.leaf_count(), "leaf index out of range");
+
+        let mut proof = Vec::new();
+        let mut position = index;
+        for layer in &self.layers[..self.layers.len() - 1] {
+            if let Some(sibling) = layer.get(position ^ 1) {
+                proof.push(*sibling);
+            }
+            position /= 2;
+        }
+        proof
+    }
+}
+
+/// Balance snapshot of every holder, for `start_distribution`
+#[derive(Clone, Debug)]
+pub struct BalanceSnapshot {
+    holders: Vec<(Pubkey, u64)>,
+    tree: MerkleTree,
+}
+
+impl BalanceSnapshot {
+    /// Build a snapshot from `(holder, balance)` pairs. Panics if `holders` is empty.
+    pub fn new(holders: Vec<(Pubkey, u64)>) -> Self {
+        let leaves = holders
+            .iter()
+            .map(|(holder, balance)| balance_leaf(holder, *balance))
+            .collect();
+        Self { tree: MerkleTree::new(leaves), holders }
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        self.tree.root()
+    }
+
+    /// Sum of all snapshot balances
+    pub fn total_balance(&self) -> u128 {
+        self.holders.iter().map(|(_, balance)| *balance as u128).sum()
+    }
+
+    /// Balance and proof for `holder`, if present
+    pub fn proof_for(&self, holder: &Pubkey) -> Option<(u64, Vec<[u8; 32]>)> {
+        let index = self.holders.iter().position(|(h, _)| h == holder)?;
+        Some((self.holders[index].1, self.tree.proof(index)))
+    }
+}
+
+/// One holder's entry in a merkle-amount distribution
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AmountClaim {
+    pub index: u32,
+    pub holder: Pubkey,
+    pub amount: u64,
+    pub proof: Vec<[u8; 32]>,
+}
+
+/// Precomputed dividend amounts, for `start_merkle_distribution`
+#[derive(Clone, Debug)]
+pub struct AmountDistribution {
+    entitlements: Vec<(Pubkey, u64)>,
+    tree: MerkleTree,
+}
+
+impl AmountDistribution {
+    /// Build a distribution from `(holder, amount)` pairs; each holder's bitmap
+    /// index is its position in the list. Panics if `entitlements` is empty or
+    /// longer than `u32::MAX`.
+    pub fn new(entitlements: Vec<(Pubkey, u64)>) -> Self {
+        assert!(entitlements.len() <= u32::MAX as usize, "too many holders");
+
+        let leaves = entitlements
+            .iter()
+            .enumerate()
+            .map(|(index, (holder, amount))| amount_leaf(index as u32, holder, *amount))
+            .collect();
+        Self { tree: MerkleTree::new(leaves), entitlements }
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        self.tree.root()
+    }
+
+    pub fn holder_count(&self) -> u32 {
+        self.entitlements.len() as u32
+    }
+
+    /// Sum of all amounts; the distribution's `total_amount`
+    pub fn total_amount(&self) -> u128 {
+        self.entitlements.iter().map(|(_, amount)| *amount as u128).sum()
+    }
+
+    /// Claim arguments for the holder at `index`
+    pub fn claim(&self, index: u32) -> AmountClaim {
+        let (holder, amount) = self.entitlements[index as usize];
+        AmountClaim {
+            index,
+            holder,
+            amount,
+            proof: self.tree.proof(index as usize),
+        }
+    }
+
+    /// Claim arguments for `holder`, if present
+    pub fn claim_for(&self, holder: &Pubkey) -> Option<AmountClaim> {
+        let index = self.entitlements.iter().position(|(h, _)| h == holder)?;
+        Some(self.claim(index as u32))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn holders(count: usize) -> Vec<(Pubkey, u64)> {
+        (0..count)
+            .map(|i| (Pubkey::new_unique(), 1_000 + i as u64 * 7))
+            .collect()
+    }
+
+    #[test]
+    fn amount_proofs_round_trip() {
+        for count in [1, 2, 3, 4, 5, 7, 8, 33, 100] {
+            let distribution = AmountDistribution::new(holders(count));
+            let root = distribution.root();
+
+            for index in 0..distribution.holder_count() {
+                let claim = distribution.claim(index);
+                let leaf = amount_leaf(claim.index, &claim.holder, claim.amount);
+                assert!(verify_proof(&root, leaf, &claim.proof), "count {count}, index {index}");
+            }
+        }
+    }
+
+    #[test]
+    fn balance_proofs_round_trip() {
+        for count in [1, 2, 6, 17, 64] {
+            let list = holders(count);
+            let snapshot = BalanceSnapshot::new(list.clone());
+
+            for (holder, balance) in &list {
+                let (proven_balance, proof) = snapshot.proof_for(holder).unwrap();
+                assert_eq!(proven_balance, *balance);
+                assert!(verify_proof(&snapshot.root(), balance_leaf(holder, *balance), &proof));
+            }
+        }
+    }
+
+    #[test]
+    fn tampered_claims_are_rejected() {
+        let distribution = AmountDistribution::new(holders(9));
+        let root = distribution.root();
+        let claim = distribution.claim(4);
+
+        let inflated = amount_leaf(claim.index, &claim.holder, claim.amount + 1);
+        assert!(!verify_proof(&root, inflated, &claim.proof));
+
+        let other_holder = amount_leaf(claim.index, &Pubkey::new_unique(), claim.amount);
+        assert!(!verify_proof(&root, other_holder, &claim.proof));
+
+        let other_index = amount_leaf(claim.index + 1, &claim.holder, claim.amount);
+        assert!(!verify_proof(&root, other_index, &claim.proof));
+
+        let mut bad_proof = claim.proof.clone();
+        bad_proof[0][0] ^= 1;
+        let leaf = amount_leaf(claim.index, &claim.holder, claim.amount);
+        assert!(!verify_proof(&root, leaf, &bad_proof));
+    }
+
+    #[test]
+    fn leaf_kinds_are_domain_separated() {
+        let holder = Pubkey::new_unique();
+        let snapshot = BalanceSnapshot::new(vec![(holder, 10)]);
+        let (_, proof) = snapshot.proof_for(&holder).unwrap();
+
+        // A balance proof must not verify as an amount claim for the same values
+        assert!(verify_proof(&snapshot.root(), balance_leaf(&holder, 10), &proof));
+        assert!(!verify_proof(&snapshot.root(), amount_leaf(0, &holder, 10), &proof));
+    }
+
+    #[test]
+    fn totals_and_lookup() {
+        let list = holders(5);
+        let expected: u128 = list.iter().map(|(_, amount)| *amount as u128).sum();
+        let distribution = AmountDistribution::new(list.clone());
+
+        assert_eq!(distribution.total_amount(), expected);
+        assert_eq!(distribution.holder_count(), 5);
+
+        let claim = distribution.claim_for(&list[3].0).unwrap();
+        assert_eq!(claim.index, 3);
+        assert_eq!(claim.amount, list[3].1);
+        assert!(distribution.claim_for(&Pubkey::new_unique()).is_none());
+    }
+}
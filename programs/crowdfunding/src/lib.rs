@@ -1,5 +1,8 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer, MintTo};
+use anchor_lang::solana_program::program_option::COption;
+use anchor_spl::associated_token::{self, get_associated_token_address, AssociatedToken};
+use anchor_spl::token::{self, Mint, Token, TokenAccount, MintTo};
+use property_dividends::program::PropertyDividends;
 
 declare_id!("2ghvWvTvMHdACLuGztbjERbvMPwwQcFTag6a6eU7RifY");
 
@@ -17,6 +20,10 @@ pub mod crowdfunding {
         config.platform_wallet = platform_wallet;
         config.total_campaigns = 0;
         config.bump = ctx.bumps.platform_config;
+        config.bond_lamports = 0;
+        config.bond_bps = 0;
+        config.bond_holding_period = 0;
+        config.referral_share_bps = 0;
         
         emit!(PlatformInitialized {
             admin: config.admin,
@@ -55,6 +62,38 @@ pub mod crowdfunding {
         Ok(())
     }
 
+    /// Register a wallet as a referral affiliate (only admin)
+    pub fn register_affiliate(ctx: Context<RegisterAffiliate>) -> Result<()> {
+        let affiliate = &mut ctx.accounts.affiliate;
+        affiliate.wallet = ctx.accounts.affiliate_wallet.key();
+        affiliate.registered_by = ctx.accounts.admin.key();
+        affiliate.registered_at = Clock::get()?.unix_timestamp;
+        affiliate.is_active = true;
+        affiliate.total_referred = 0;
+        affiliate.total_earned = 0;
+        affiliate.bump = ctx.bumps.affiliate;
+        
+        emit!(AffiliateRegistered {
+            affiliate: affiliate.wallet,
+            registered_by: affiliate.registered_by,
+        });
+        
+        Ok(())
+    }
+
+    /// Deactivate an affiliate so new investments can no longer reference it (only admin).
+    /// Rewards already accrued remain claimable.
+    pub fn deactivate_affiliate(ctx: Context<DeactivateAffiliate>) -> Result<()> {
+        let affiliate = &mut ctx.accounts.affiliate;
+        affiliate.is_active = false;
+        
+        emit!(AffiliateDeactivated {
+            affiliate: affiliate.wallet,
+        });
+        
+        Ok(())
+    }
+
     /// Create a new crowdfunding campaign (only whitelisted wallets)
     pub fn create_campaign(
         ctx: Context<CreateCampaign>,
@@ -96,6 +135,58 @@ pub mod crowdfunding {
         campaign.bump = ctx.bumps.campaign;
         campaign.escrow_bump = ctx.bumps.escrow_vault;
         
+        // Performance bond: the larger of the flat and goal-relative requirement
+        let config = &ctx.accounts.platform_config;
+        let goal_bond = (funding_goal as u128)
+            .checked_mul(config.bond_bps as u128)
+            .ok_or(CrowdfundingError::Overflow)?
+            .checked_div(10000)
+            .ok_or(CrowdfundingError::Overflow)? as u64;
+        let bond_amount = config.bond_lamports.max(goal_bond);
+        
+        // The bond vault is a plain system account, so a non-zero bond has to
+        // cover its rent-exempt minimum.
+        require!(
+            bond_amount == 0 || bond_amount >= ctx.accounts.rent.minimum_balance(0),
+            CrowdfundingError::BondBelowRentMinimum
+        );
+        
+        if bond_amount > 0 {
+            let cpi_context = CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.creator.to_account_info(),
+                    to: ctx.accounts.bond_vault.to_account_info(),
+                },
+            );
+            anchor_lang::system_program::transfer(cpi_context, bond_amount)?;
+        }
+        
+        campaign.bond_amount = bond_amount;
+        campaign.bond_status = if bond_amount > 0 { BondStatus::Held } else { BondStatus::Released };
+        campaign.bond_release_at = 0;
+        campaign.bond_bump = ctx.bumps.bond_vault;
+        campaign.bond_claimed_invested = 0;
+        
+        // Claim the property in the global registry. A property can only be
+        // relisted once its previous campaign was cancelled.
+        let registry = &mut ctx.accounts.property_registry;
+        if registry.campaign != Pubkey::default() {
+            require!(
+                registry.status == CampaignStatus::Cancelled,
+                CrowdfundingError::PropertyAlreadyRegistered
+            );
+        }
+        registry.property_id = normalize_property_id(&property_id);
+        registry.campaign = campaign.key();
+        registry.creator = campaign.creator;
+        registry.property_mint = campaign.property_mint;
+        registry.dividend_pool = Pubkey::default();
+        registry.status = CampaignStatus::Active;
+        registry.registered_at = campaign.created_at;
+        registry.bump = ctx.bumps.property_registry;
+        campaign.registered = true;
+        
         // Update platform stats
         let config = &mut ctx.accounts.platform_config;
         config.total_campaigns = config.total_campaigns.checked_add(1).ok_or(CrowdfundingError::Overflow)?;
@@ -116,6 +207,7 @@ pub mod crowdfunding {
             platform_tokens,
             tokens_available: total_tokens - platform_tokens,
             deadline: funding_deadline,
+            bond_amount,
         });
         
         Ok(())
@@ -131,6 +223,10 @@ pub mod crowdfunding {
         require!(campaign.status == CampaignStatus::Active, CrowdfundingError::CampaignNotActive);
         require!(amount > 0, CrowdfundingError::InvalidAmount);
         require!(amount >= campaign.token_price, CrowdfundingError::AmountBelowMinimum);
+        require!(
+            ctx.accounts.referrer.is_some() == ctx.accounts.referral_record.is_some(),
+            CrowdfundingError::MissingReferralRecord
+        );
         
         // Calculate tokens to purchase
         let tokens_to_buy = amount.checked_div(campaign.token_price).ok_or(CrowdfundingError::Overflow)?;
@@ -176,6 +272,41 @@ pub mod crowdfunding {
         investor_record.tokens_claimed = false;
         investor_record.bump = ctx.bumps.investor_record;
         
+        // Attribute the investment to a registered affiliate. The first referrer
+        // recorded for an investor sticks for the rest of the campaign.
+        if let Some(affiliate) = ctx.accounts.referrer.as_mut() {
+            require!(affiliate.is_active, CrowdfundingError::AffiliateNotActive);
+            require!(
+                affiliate.wallet != ctx.accounts.investor.key(),
+                CrowdfundingError::SelfReferral
+            );
+            match investor_record.referrer {
+                Some(existing) => require!(
+                    existing == affiliate.wallet,
+                    CrowdfundingError::ReferrerMismatch
+                ),
+                None => investor_record.referrer = Some(affiliate.wallet),
+            }
+            
+            let referral_record = ctx
+                .accounts
+                .referral_record
+                .as_mut()
+                .ok_or(CrowdfundingError::MissingReferralRecord)?;
+            referral_record.affiliate = affiliate.wallet;
+            referral_record.campaign = campaign.key();
+            referral_record.referred_amount = referral_record.referred_amount
+                .checked_add(amount)
+                .ok_or(CrowdfundingError::Overflow)?;
+            referral_record.bump = ctx.bumps.referral_record.ok_or(CrowdfundingError::MissingReferralRecord)?;
+            
+            affiliate.total_referred = affiliate.total_referred
+                .checked_add(amount)
+                .ok_or(CrowdfundingError::Overflow)?;
+        }
+        let referrer = investor_record.referrer;
+        let referred = ctx.accounts.referrer.is_some();
+        
         // Update campaign
         let campaign = &mut ctx.accounts.campaign;
         campaign.total_raised = campaign.total_raised
@@ -185,6 +316,12 @@ pub mod crowdfunding {
             .checked_add(tokens_to_buy)
             .ok_or(CrowdfundingError::Overflow)?;
         
+        if referred {
+            campaign.total_referred = campaign.total_referred
+                .checked_add(amount)
+                .ok_or(CrowdfundingError::Overflow)?;
+        }
+        
         if is_new_investor {
             campaign.investor_count = campaign.investor_count
                 .checked_add(1)
@@ -196,7 +333,8 @@ pub mod crowdfunding {
             investor: ctx.accounts.investor.key(),
             amount,
             tokens_purchased: tokens_to_buy,
-            total_invested: investor_record.amount_invested,
+            total_invested: ctx.accounts.investor_record.amount_invested,
+            referrer,
         });
         
         Ok(())
@@ -205,11 +343,24 @@ pub mod crowdfunding {
     /// Finalize a campaign (creator only). Can be called any time as long as
     /// at least 1 lamport has been raised. After finalization investors can
     /// claim their tokens via `claim_tokens`.
-    pub fn finalize_campaign(ctx: Context<FinalizeCampaign>) -> Result<()> {
+    ///
+    /// Also creates the property's dividend pool in `property_dividends`, with the
+    /// creator (or `property_manager`, if given) as its authority. An existing
+    /// pool must already belong to one of them; if the creator holds it, it is
+    /// handed to `property_manager`.
+    pub fn finalize_campaign(
+        ctx: Context<FinalizeCampaign>,
+        distribution_frequency_days: u64,
+        property_manager: Option<Pubkey>,
+    ) -> Result<()> {
         let campaign = &ctx.accounts.campaign;
         
         require!(campaign.status == CampaignStatus::Active, CrowdfundingError::CampaignNotActive);
         require!(campaign.total_raised > 0, CrowdfundingError::CannotFinalizeYet);
+        require!(
+            ctx.accounts.property_registry.is_some() || !campaign.registered,
+            CrowdfundingError::MissingPropertyRegistry
+        );
         
         // Calculate platform share
         let platform_share = (campaign.total_raised as u128)
@@ -222,8 +373,30 @@ pub mod crowdfunding {
             .checked_sub(platform_share)
             .ok_or(CrowdfundingError::Overflow)?;
         
+        // Affiliates' cut of the platform share stays in escrow until claimed
+        let referral_share_bps = ctx.accounts.platform_config.referral_share_bps;
+        let referral_share = referral_reward(
+            campaign.total_referred,
+            campaign.platform_equity_bps,
+            referral_share_bps,
+        )?;
+        let platform_payout = platform_share
+            .checked_sub(referral_share)
+            .ok_or(CrowdfundingError::Overflow)?;
+        
+        // While referral rewards are outstanding the escrow also keeps its
+        // rent-exempt minimum, taken from the creator's share, so small rewards
+        // never leave it below rent. The last affiliate to claim closes it and
+        // the reserve goes back to the creator.
+        let escrow_reserve = if referral_share > 0 {
+            ctx.accounts.rent.minimum_balance(0)
+        } else {
+            0
+        };
+        let creator_payout = creator_share.saturating_sub(escrow_reserve);
+        
         // Transfer to platform wallet
-        if platform_share > 0 {
+        if platform_payout > 0 {
             let campaign_key = campaign.key();
             let seeds = &[
                 b"escrow",
@@ -242,12 +415,12 @@ pub mod crowdfunding {
                     transfer_to_platform,
                     signer_seeds,
                 ),
-                platform_share,
+                platform_payout,
             )?;
         }
         
         // Transfer to creator
-        if creator_share > 0 {
+        if creator_payout > 0 {
             let campaign_key = campaign.key();
             let seeds = &[
                 b"escrow",
@@ -266,20 +439,91 @@ pub mod crowdfunding {
                     transfer_to_creator,
                     signer_seeds,
                 ),
-                creator_share,
+                creator_payout,
             )?;
         }
         
-        // Update campaign status
+        // Update campaign status; the bond is held for the configured period
         let campaign = &mut ctx.accounts.campaign;
         campaign.status = CampaignStatus::Funded;
+        campaign.referral_share_bps = referral_share_bps;
+        if let Some(registry) = ctx.accounts.property_registry.as_mut() {
+            registry.status = CampaignStatus::Funded;
+        }
+        campaign.bond_release_at = Clock::get()?
+            .unix_timestamp
+            .checked_add(ctx.accounts.platform_config.bond_holding_period)
+            .ok_or(CrowdfundingError::Overflow)?;
+        
+        // Create the dividend pool for the property mint. A pool that already
+        // exists for the mint is linked only if the creator or the property
+        // manager controls it.
+        let creator = ctx.accounts.creator.key();
+        let pool_authority = if ctx.accounts.dividend_pool.data_is_empty() {
+            // `initialize_pool` checks the campaign is funded, so persist the
+            // status before handing the account over.
+            ctx.accounts.campaign.exit(&crate::ID)?;
+
+            property_dividends::cpi::initialize_pool(
+                CpiContext::new(
+                    ctx.accounts.dividends_program.to_account_info(),
+                    property_dividends::cpi::accounts::InitializePool {
+                        authority: ctx.accounts.creator.to_account_info(),
+                        property_mint: ctx.accounts.property_mint.to_account_info(),
+                        dividend_pool: ctx.accounts.dividend_pool.to_account_info(),
+                        dividend_vault: ctx.accounts.dividend_vault.to_account_info(),
+                        tax_vault: ctx.accounts.tax_vault.to_account_info(),
+                        reserve_vault: ctx.accounts.reserve_vault.to_account_info(),
+                        payout_mint: None,
+                        token_vault: None,
+                        campaign: Some(ctx.accounts.campaign.to_account_info()),
+                        platform_config: None,
+                        system_program: ctx.accounts.system_program.to_account_info(),
+                        token_program: ctx.accounts.token_program.to_account_info(),
+                        rent: ctx.accounts.rent.to_account_info(),
+                    },
+                ),
+                ctx.accounts.campaign.property_id.clone(),
+                distribution_frequency_days,
+            )?;
+            creator
+        } else {
+            require_keys_eq!(
+                *ctx.accounts.dividend_pool.owner,
+                property_dividends::ID,
+                CrowdfundingError::PoolAuthorityMismatch
+            );
+            let data = ctx.accounts.dividend_pool.try_borrow_data()?;
+            property_dividends::DividendPool::try_deserialize(&mut &data[..])?.authority
+        };
+        
+        let expected_authority = property_manager.unwrap_or(creator);
+        if pool_authority != expected_authority {
+            require_keys_eq!(pool_authority, creator, CrowdfundingError::PoolAuthorityMismatch);
+            property_dividends::cpi::update_authority(
+                CpiContext::new(
+                    ctx.accounts.dividends_program.to_account_info(),
+                    property_dividends::cpi::accounts::UpdateAuthority {
+                        authority: ctx.accounts.creator.to_account_info(),
+                        dividend_pool: ctx.accounts.dividend_pool.to_account_info(),
+                    },
+                ),
+                expected_authority,
+            )?;
+        }
+        if let Some(registry) = ctx.accounts.property_registry.as_mut() {
+            registry.dividend_pool = ctx.accounts.dividend_pool.key();
+        }
         
+        let campaign = &ctx.accounts.campaign;
         emit!(CampaignFinalized {
             campaign: campaign.key(),
             total_raised: campaign.total_raised,
             platform_share,
-            creator_share,
+            creator_share: creator_payout,
             investors: campaign.investor_count,
+            referral_share,
+            dividend_pool: ctx.accounts.dividend_pool.key(),
         });
         
         Ok(())
@@ -296,6 +540,10 @@ pub mod crowdfunding {
         );
         
         campaign.status = CampaignStatus::Cancelled;
+        match ctx.accounts.property_registry.as_mut() {
+            Some(registry) => registry.status = CampaignStatus::Cancelled,
+            None => require!(!campaign.registered, CrowdfundingError::MissingPropertyRegistry),
+        }
         
         emit!(CampaignCancelled {
             campaign: campaign.key(),
@@ -313,9 +561,9 @@ pub mod crowdfunding {
         
         require!(campaign.status == CampaignStatus::Cancelled, CrowdfundingError::CampaignNotCancelled);
         require!(!investor_record.refunded, CrowdfundingError::AlreadyRefunded);
-        require!(investor_record.amount_invested > 0, CrowdfundingError::NothingToRefund);
         
-        let refund_amount = investor_record.amount_invested;
+        let refund_amount = investor_record.refund_due();
+        require!(refund_amount > 0, CrowdfundingError::NothingToRefund);
         
         // Transfer from escrow to investor
         let campaign_key = campaign.key();
@@ -352,6 +600,81 @@ pub mod crowdfunding {
         Ok(())
     }
 
+    /// Refund a batch of investors of a cancelled campaign (permissionless crank).
+    /// `remaining_accounts` holds `[investor_record, investor]` pairs; refunds always
+    /// go to the investor recorded on the record, and refunded records are skipped.
+    pub fn process_refunds<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ProcessRefunds<'info>>,
+    ) -> Result<()> {
+        let campaign = &ctx.accounts.campaign;
+        
+        require!(campaign.status == CampaignStatus::Cancelled, CrowdfundingError::CampaignNotCancelled);
+        require!(
+            !ctx.remaining_accounts.is_empty()
+                && ctx.remaining_accounts.chunks_exact(2).remainder().is_empty(),
+            CrowdfundingError::InvalidRemainingAccounts
+        );
+        
+        let campaign_key = campaign.key();
+        let seeds = &[
+            b"escrow",
+            campaign_key.as_ref(),
+            &[campaign.escrow_bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+        
+        let mut investors_refunded: u32 = 0;
+        let mut total_refunded: u64 = 0;
+        
+        for accounts in ctx.remaining_accounts.chunks_exact(2) {
+            let (record_info, investor_info) = (&accounts[0], &accounts[1]);
+            
+            let mut investor_record = Account::<InvestorRecord>::try_from(record_info)?;
+            require_keys_eq!(investor_record.campaign, campaign_key, CrowdfundingError::InvalidInvestorRecord);
+            require_keys_eq!(investor_record.investor, investor_info.key(), CrowdfundingError::InvalidInvestorRecord);
+            
+            let refund_amount = investor_record.refund_due();
+            if refund_amount == 0 {
+                continue;
+            }
+            
+            anchor_lang::system_program::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.escrow_vault.to_account_info(),
+                        to: investor_info.clone(),
+                    },
+                    signer_seeds,
+                ),
+                refund_amount,
+            )?;
+            
+            investor_record.refunded = true;
+            investor_record.exit(&crate::ID)?;
+            
+            investors_refunded = investors_refunded.checked_add(1).ok_or(CrowdfundingError::Overflow)?;
+            total_refunded = total_refunded
+                .checked_add(refund_amount)
+                .ok_or(CrowdfundingError::Overflow)?;
+            
+            emit!(RefundClaimed {
+                campaign: campaign_key,
+                investor: investor_info.key(),
+                amount: refund_amount,
+            });
+        }
+        
+        emit!(RefundsProcessed {
+            campaign: campaign_key,
+            processed_by: ctx.accounts.payer.key(),
+            investors: investors_refunded,
+            amount: total_refunded,
+        });
+        
+        Ok(())
+    }
+
     /// Claim property tokens (investor only, when campaign is funded)
     pub fn claim_tokens(ctx: Context<ClaimTokens>) -> Result<()> {
         let campaign = &ctx.accounts.campaign;
@@ -359,12 +682,11 @@ pub mod crowdfunding {
         
         require!(campaign.status == CampaignStatus::Funded, CrowdfundingError::CampaignNotFunded);
         require!(!investor_record.tokens_claimed, CrowdfundingError::TokensAlreadyClaimed);
-        require!(investor_record.tokens_purchased > 0, CrowdfundingError::NoTokensToClaim);
         
-        let tokens_to_mint = investor_record.tokens_purchased;
+        let tokens_to_mint = investor_record.tokens_due();
+        require!(tokens_to_mint > 0, CrowdfundingError::NoTokensToClaim);
         
         // Mint tokens to investor
-        let campaign_key = campaign.key();
         let seeds = &[
             b"campaign",
             campaign.property_id.as_bytes(),
@@ -397,6 +719,101 @@ pub mod crowdfunding {
         Ok(())
     }
 
+    /// Mint purchased tokens to a batch of investors (permissionless crank, caller pays
+    /// any ATA rent). `remaining_accounts` holds `[investor_record, investor, investor_ata]`
+    /// triples; records that are already claimed are skipped.
+    pub fn distribute_tokens<'info>(
+        ctx: Context<'_, '_, 'info, 'info, DistributeTokens<'info>>,
+    ) -> Result<()> {
+        let campaign = &ctx.accounts.campaign;
+        
+        require!(campaign.status == CampaignStatus::Funded, CrowdfundingError::CampaignNotFunded);
+        require!(
+            !ctx.remaining_accounts.is_empty()
+                && ctx.remaining_accounts.chunks_exact(3).remainder().is_empty(),
+            CrowdfundingError::InvalidRemainingAccounts
+        );
+        
+        let campaign_key = campaign.key();
+        let seeds = &[
+            b"campaign",
+            campaign.property_id.as_bytes(),
+            campaign.creator.as_ref(),
+            &[campaign.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+        
+        let mut investors_settled: u32 = 0;
+        let mut tokens_distributed: u64 = 0;
+        
+        for accounts in ctx.remaining_accounts.chunks_exact(3) {
+            let (record_info, investor_info, token_account_info) = (&accounts[0], &accounts[1], &accounts[2]);
+            
+            let mut investor_record = Account::<InvestorRecord>::try_from(record_info)?;
+            require_keys_eq!(investor_record.campaign, campaign_key, CrowdfundingError::InvalidInvestorRecord);
+            require_keys_eq!(investor_record.investor, investor_info.key(), CrowdfundingError::InvalidInvestorRecord);
+            
+            let tokens_to_mint = investor_record.tokens_due();
+            if tokens_to_mint == 0 {
+                continue;
+            }
+            
+            require_keys_eq!(
+                token_account_info.key(),
+                get_associated_token_address(&investor_info.key(), &campaign.property_mint),
+                CrowdfundingError::InvalidTokenOwner
+            );
+            
+            associated_token::create_idempotent(CpiContext::new(
+                ctx.accounts.associated_token_program.to_account_info(),
+                associated_token::Create {
+                    payer: ctx.accounts.payer.to_account_info(),
+                    associated_token: token_account_info.clone(),
+                    authority: investor_info.clone(),
+                    mint: ctx.accounts.property_mint.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                },
+            ))?;
+            
+            token::mint_to(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    MintTo {
+                        mint: ctx.accounts.property_mint.to_account_info(),
+                        to: token_account_info.clone(),
+                        authority: ctx.accounts.campaign.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                tokens_to_mint,
+            )?;
+            
+            investor_record.tokens_claimed = true;
+            investor_record.exit(&crate::ID)?;
+            
+            investors_settled = investors_settled.checked_add(1).ok_or(CrowdfundingError::Overflow)?;
+            tokens_distributed = tokens_distributed
+                .checked_add(tokens_to_mint)
+                .ok_or(CrowdfundingError::Overflow)?;
+            
+            emit!(TokensClaimed {
+                campaign: campaign_key,
+                investor: investor_info.key(),
+                tokens: tokens_to_mint,
+            });
+        }
+        
+        emit!(TokensDistributed {
+            campaign: campaign_key,
+            distributed_by: ctx.accounts.payer.key(),
+            investors: investors_settled,
+            tokens: tokens_distributed,
+        });
+        
+        Ok(())
+    }
+
     /// Update platform wallet (admin only)
     pub fn update_platform_wallet(
         ctx: Context<UpdatePlatformConfig>,
@@ -413,53 +830,393 @@ pub mod crowdfunding {
         
         Ok(())
     }
-}
 
-// ============================================================================
-// Account Structures
-// ============================================================================
+    /// Update the creator performance bond requirement (admin only).
+    /// The bond is the larger of `bond_lamports` and `bond_bps` of the funding goal.
+    pub fn update_bond_config(
+        ctx: Context<UpdatePlatformConfig>,
+        bond_lamports: u64,
+        bond_bps: u16,
+        bond_holding_period: i64,
+    ) -> Result<()> {
+        require!(bond_bps <= 10000, CrowdfundingError::InvalidBondConfig);
+        require!(bond_holding_period >= 0, CrowdfundingError::InvalidBondConfig);
+        
+        let config = &mut ctx.accounts.platform_config;
+        config.bond_lamports = bond_lamports;
+        config.bond_bps = bond_bps;
+        config.bond_holding_period = bond_holding_period;
+        
+        emit!(BondConfigUpdated {
+            bond_lamports,
+            bond_bps,
+            bond_holding_period,
+        });
+        
+        Ok(())
+    }
 
-#[account]
-#[derive(Default)]
-pub struct PlatformConfig {
-    pub admin: Pubkey,
-    pub platform_wallet: Pubkey,
-    pub total_campaigns: u64,
-    pub bump: u8,
-}
+    /// Return the performance bond to the creator (creator only). Allowed once the
+    /// holding period after finalization has passed, or if the campaign was cancelled
+    /// before raising anything.
+    pub fn release_bond(ctx: Context<ReleaseBond>) -> Result<()> {
+        let campaign = &ctx.accounts.campaign;
+        
+        require!(campaign.bond_status == BondStatus::Held, CrowdfundingError::BondNotHeld);
+        let releasable = match campaign.status {
+            CampaignStatus::Funded => Clock::get()?.unix_timestamp >= campaign.bond_release_at,
+            CampaignStatus::Cancelled => campaign.total_raised == 0,
+            CampaignStatus::Active => false,
+        };
+        require!(releasable, CrowdfundingError::BondLocked);
+        
+        let amount = campaign.bond_amount;
+        let campaign_key = campaign.key();
+        let seeds = &[
+            b"bond",
+            campaign_key.as_ref(),
+            &[campaign.bond_bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+        
+        let transfer_bond = anchor_lang::system_program::Transfer {
+            from: ctx.accounts.bond_vault.to_account_info(),
+            to: ctx.accounts.creator.to_account_info(),
+        };
+        anchor_lang::system_program::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                transfer_bond,
+                signer_seeds,
+            ),
+            amount,
+        )?;
+        
+        let campaign = &mut ctx.accounts.campaign;
+        campaign.bond_status = BondStatus::Released;
+        
+        emit!(BondReleased {
+            campaign: campaign.key(),
+            creator: campaign.creator,
+            amount,
+        });
+        
+        Ok(())
+    }
 
-#[account]
-#[derive(Default)]
-pub struct WhitelistEntry {
-    pub wallet: Pubkey,
-    pub whitelisted_by: Pubkey,
-    pub whitelisted_at: i64,
-    pub is_active: bool,
-    pub bump: u8,
-}
+    /// Slash the creator's bond to the campaign's investors (admin only), e.g. when
+    /// the creator cancels after raising or is found in breach. The slashed amount
+    /// stays in the bond vault and is claimed pro-rata via `claim_bond_share`.
+    /// Only closed campaigns can be slashed, so `total_raised` no longer changes
+    /// while investors claim.
+    pub fn slash_bond(ctx: Context<SlashBond>) -> Result<()> {
+        let campaign = &mut ctx.accounts.campaign;
+        
+        require!(campaign.bond_status == BondStatus::Held, CrowdfundingError::BondNotHeld);
+        require!(campaign.status != CampaignStatus::Active, CrowdfundingError::CampaignStillActive);
+        require!(campaign.total_raised > 0, CrowdfundingError::NoInvestorsToCompensate);
+        
+        campaign.bond_status = BondStatus::Slashed;
+        
+        emit!(BondSlashed {
+            campaign: campaign.key(),
+            creator: campaign.creator,
+            amount: campaign.bond_amount,
+            slashed_by: ctx.accounts.admin.key(),
+        });
+        
+        Ok(())
+    }
 
-#[account]
-#[derive(Default)]
-pub struct Campaign {
-    pub creator: Pubkey,
-    pub property_mint: Pubkey,
-    pub escrow_vault: Pubkey,
-    pub property_id: String,
-    pub funding_goal: u64,
-    pub total_raised: u64,
-    pub platform_equity_bps: u16,
-    pub funding_deadline: i64,
-    pub token_price: u64,
-    pub total_tokens: u64,
-    pub tokens_sold: u64,
-    pub investor_count: u32,
-    pub status: CampaignStatus,
-    pub created_at: i64,
-    pub bump: u8,
-    pub escrow_bump: u8,
-}
+    /// Claim an investor's pro-rata share of a slashed bond (investor only).
+    /// The vault keeps its rent-exempt minimum until the last investor claims,
+    /// who also takes the rounding remainder and closes the vault.
+    pub fn claim_bond_share(ctx: Context<ClaimBondShare>) -> Result<()> {
+        let campaign = &ctx.accounts.campaign;
+        let investor_record = &ctx.accounts.investor_record;
+        
+        require!(campaign.bond_status == BondStatus::Slashed, CrowdfundingError::BondNotSlashed);
+        require!(!investor_record.bond_share_claimed, CrowdfundingError::BondShareAlreadyClaimed);
+        require!(investor_record.amount_invested > 0, CrowdfundingError::NothingToRefund);
+        
+        let claimed_invested = campaign.bond_claimed_invested
+            .checked_add(investor_record.amount_invested)
+            .ok_or(CrowdfundingError::Overflow)?;
+        let share = bond_share(
+            campaign.bond_amount,
+            investor_record.amount_invested,
+            campaign.total_raised,
+            claimed_invested,
+            ctx.accounts.bond_vault.lamports(),
+            ctx.accounts.rent.minimum_balance(0),
+        )?;
+        
+        if share > 0 {
+            let campaign_key = campaign.key();
+            let seeds = &[
+                b"bond",
+                campaign_key.as_ref(),
+                &[campaign.bond_bump],
+            ];
+            let signer_seeds = &[&seeds[..]];
+            
+            let transfer_share = anchor_lang::system_program::Transfer {
+                from: ctx.accounts.bond_vault.to_account_info(),
+                to: ctx.accounts.investor.to_account_info(),
+            };
+            anchor_lang::system_program::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.system_program.to_account_info(),
+                    transfer_share,
+                    signer_seeds,
+                ),
+                share,
+            )?;
+        }
+        
+        let investor_record = &mut ctx.accounts.investor_record;
+        investor_record.bond_share_claimed = true;
+        
+        let campaign = &mut ctx.accounts.campaign;
+        campaign.bond_claimed_invested = claimed_invested;
+        
+        emit!(BondShareClaimed {
+            campaign: campaign.key(),
+            investor: ctx.accounts.investor.key(),
+            amount: share,
+        });
+        
+        Ok(())
+    }
 
-#[account]
+    /// Update the affiliates' share of the platform cut (admin only).
+    /// The value in effect at finalization applies to a campaign.
+    pub fn update_referral_share(
+        ctx: Context<UpdatePlatformConfig>,
+        referral_share_bps: u16,
+    ) -> Result<()> {
+        require!(referral_share_bps <= 10000, CrowdfundingError::InvalidReferralShare);
+        
+        let config = &mut ctx.accounts.platform_config;
+        let old_share_bps = config.referral_share_bps;
+        config.referral_share_bps = referral_share_bps;
+        
+        emit!(ReferralShareUpdated {
+            old_share_bps,
+            new_share_bps: referral_share_bps,
+        });
+        
+        Ok(())
+    }
+
+    /// Claim an affiliate's referral reward for a funded campaign (affiliate only).
+    /// The last affiliate to claim also takes the escrow's rounding remainder and
+    /// closes the escrow, returning its rent reserve to the creator.
+    pub fn claim_referral_reward(ctx: Context<ClaimReferralReward>) -> Result<()> {
+        let campaign = &ctx.accounts.campaign;
+        let referral_record = &ctx.accounts.referral_record;
+        
+        require!(campaign.status == CampaignStatus::Funded, CrowdfundingError::CampaignNotFunded);
+        require!(!referral_record.reward_claimed, CrowdfundingError::ReferralRewardAlreadyClaimed);
+        
+        let mut reward = referral_reward(
+            referral_record.referred_amount,
+            campaign.platform_equity_bps,
+            campaign.referral_share_bps,
+        )?;
+        let referral_claimed = campaign.referral_claimed
+            .checked_add(referral_record.referred_amount)
+            .ok_or(CrowdfundingError::Overflow)?;
+        let last_claim = referral_claimed >= campaign.total_referred;
+        if last_claim {
+            reward = ctx
+                .accounts
+                .escrow_vault
+                .lamports()
+                .saturating_sub(ctx.accounts.rent.minimum_balance(0));
+        }
+        require!(reward > 0 || last_claim, CrowdfundingError::NoReferralReward);
+        
+        let campaign_key = campaign.key();
+        let seeds = &[
+            b"escrow",
+            campaign_key.as_ref(),
+            &[campaign.escrow_bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+        
+        if reward > 0 {
+            let transfer_reward = anchor_lang::system_program::Transfer {
+                from: ctx.accounts.escrow_vault.to_account_info(),
+                to: ctx.accounts.affiliate_wallet.to_account_info(),
+            };
+            anchor_lang::system_program::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.system_program.to_account_info(),
+                    transfer_reward,
+                    signer_seeds,
+                ),
+                reward,
+            )?;
+        }
+        
+        // The rent reserve came out of the creator's proceeds
+        let reserve = ctx.accounts.escrow_vault.lamports();
+        if last_claim && reserve > 0 {
+            let transfer_reserve = anchor_lang::system_program::Transfer {
+                from: ctx.accounts.escrow_vault.to_account_info(),
+                to: ctx.accounts.creator.to_account_info(),
+            };
+            anchor_lang::system_program::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.system_program.to_account_info(),
+                    transfer_reserve,
+                    signer_seeds,
+                ),
+                reserve,
+            )?;
+        }
+        
+        let referral_record = &mut ctx.accounts.referral_record;
+        referral_record.reward_claimed = true;
+        
+        let campaign = &mut ctx.accounts.campaign;
+        campaign.referral_claimed = referral_claimed;
+        
+        let affiliate = &mut ctx.accounts.affiliate;
+        affiliate.total_earned = affiliate.total_earned
+            .checked_add(reward)
+            .ok_or(CrowdfundingError::Overflow)?;
+        
+        emit!(ReferralRewardClaimed {
+            campaign: campaign.key(),
+            affiliate: affiliate.wallet,
+            amount: reward,
+        });
+        
+        Ok(())
+    }
+
+    /// Grow an investor record created before referral attribution to the current
+    /// layout (anyone may pay). Such records must be resized before their investor
+    /// invests in the campaign again.
+    pub fn resize_investor_record(_ctx: Context<ResizeInvestorRecord>) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Canonical form of a property identifier used by the property registry
+pub fn normalize_property_id(property_id: &str) -> String {
+    property_id.trim().to_ascii_lowercase()
+}
+
+/// Registry PDA seed for a property: the hash of its normalized identifier, so
+/// identifiers up to 64 bytes fit in a single seed.
+/// PDA: `["property", hash(normalize_property_id(property_id))]`
+pub fn property_registry_seed(property_id: &str) -> [u8; 32] {
+    anchor_lang::solana_program::hash::hash(normalize_property_id(property_id).as_bytes()).to_bytes()
+}
+
+/// Share of a slashed bond for an investor who put in `invested`, where
+/// `claimed_invested` totals the investments whose shares are paid, this one
+/// included. The last investor to claim takes the whole `vault_balance`; others
+/// get their pro-rata share of `bond_amount`, leaving the vault's `rent_minimum`.
+fn bond_share(
+    bond_amount: u64,
+    invested: u64,
+    total_raised: u64,
+    claimed_invested: u64,
+    vault_balance: u64,
+    rent_minimum: u64,
+) -> Result<u64> {
+    if claimed_invested >= total_raised {
+        return Ok(vault_balance);
+    }
+    let pro_rata = (bond_amount as u128)
+        .checked_mul(invested as u128)
+        .ok_or(CrowdfundingError::Overflow)?
+        .checked_div(total_raised as u128)
+        .ok_or(CrowdfundingError::Overflow)? as u64;
+    Ok(pro_rata.min(vault_balance.saturating_sub(rent_minimum)))
+}
+
+/// Wallet of the optional referrer, used to derive the referral record PDA.
+/// Without a referrer the seed never matches a valid record.
+fn referrer_wallet(referrer: &Option<Account<Affiliate>>) -> Pubkey {
+    referrer.as_ref().map(|affiliate| affiliate.wallet).unwrap_or_default()
+}
+
+/// Affiliate reward for `referred_amount`: `referral_share_bps` of the platform's
+/// `platform_equity_bps` cut of the referred investment.
+fn referral_reward(referred_amount: u64, platform_equity_bps: u16, referral_share_bps: u16) -> Result<u64> {
+    let reward = (referred_amount as u128)
+        .checked_mul(platform_equity_bps as u128)
+        .ok_or(CrowdfundingError::Overflow)?
+        .checked_mul(referral_share_bps as u128)
+        .ok_or(CrowdfundingError::Overflow)?
+        .checked_div(10000 * 10000)
+        .ok_or(CrowdfundingError::Overflow)?;
+    Ok(reward as u64)
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[account]
+#[derive(Default)]
+pub struct PlatformConfig {
+    pub admin: Pubkey,
+    pub platform_wallet: Pubkey,
+    pub total_campaigns: u64,
+    pub bump: u8,
+    pub bond_lamports: u64,
+    pub bond_bps: u16,
+    pub bond_holding_period: i64,
+    pub referral_share_bps: u16,
+}
+
+#[account]
+#[derive(Default)]
+pub struct WhitelistEntry {
+    pub wallet: Pubkey,
+    pub whitelisted_by: Pubkey,
+    pub whitelisted_at: i64,
+    pub is_active: bool,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(Default)]
+pub struct Campaign {
+    pub creator: Pubkey,
+    pub property_mint: Pubkey,
+    pub escrow_vault: Pubkey,
+    pub property_id: String,
+    pub funding_goal: u64,
+    pub total_raised: u64,
+    pub platform_equity_bps: u16,
+    pub funding_deadline: i64,
+    pub token_price: u64,
+    pub total_tokens: u64,
+    pub tokens_sold: u64,
+    pub investor_count: u32,
+    pub status: CampaignStatus,
+    pub created_at: i64,
+    pub bump: u8,
+    pub escrow_bump: u8,
+    pub bond_amount: u64,
+    pub bond_status: BondStatus,
+    pub bond_release_at: i64,
+    pub bond_bump: u8,
+    pub total_referred: u64,
+    pub referral_share_bps: u16,
+    pub bond_claimed_invested: u64,
+    pub referral_claimed: u64,
+    pub registered: bool,
+}
+
+#[account]
 #[derive(Default)]
 pub struct InvestorRecord {
     pub investor: Pubkey,
@@ -470,6 +1227,63 @@ pub struct InvestorRecord {
     pub refunded: bool,
     pub tokens_claimed: bool,
     pub bump: u8,
+    pub bond_share_claimed: bool,
+    pub referrer: Option<Pubkey>,
+}
+
+impl InvestorRecord {
+    /// Property tokens still to be minted to the investor
+    pub fn tokens_due(&self) -> u64 {
+        if self.tokens_claimed {
+            0
+        } else {
+            self.tokens_purchased
+        }
+    }
+
+    /// Lamports still to be refunded to the investor
+    pub fn refund_due(&self) -> u64 {
+        if self.refunded {
+            0
+        } else {
+            self.amount_invested
+        }
+    }
+}
+
+#[account]
+#[derive(Default)]
+pub struct PropertyRegistry {
+    pub property_id: String,
+    pub campaign: Pubkey,
+    pub creator: Pubkey,
+    pub property_mint: Pubkey,
+    pub dividend_pool: Pubkey,
+    pub status: CampaignStatus,
+    pub registered_at: i64,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(Default)]
+pub struct Affiliate {
+    pub wallet: Pubkey,
+    pub registered_by: Pubkey,
+    pub registered_at: i64,
+    pub is_active: bool,
+    pub total_referred: u64,
+    pub total_earned: u64,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(Default)]
+pub struct ReferralRecord {
+    pub affiliate: Pubkey,
+    pub campaign: Pubkey,
+    pub referred_amount: u64,
+    pub reward_claimed: bool,
+    pub bump: u8,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Default)]
@@ -480,6 +1294,14 @@ pub enum CampaignStatus {
     Cancelled,
 }
 
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Default)]
+pub enum BondStatus {
+    #[default]
+    Held,
+    Released,
+    Slashed,
+}
+
 // ============================================================================
 // Contexts
 // ============================================================================
@@ -492,7 +1314,7 @@ pub struct InitializePlatform<'info> {
     #[account(
         init,
         payer = admin,
-        space = 8 + 32 + 32 + 8 + 1 + 32,
+        space = 8 + 32 + 32 + 8 + 1 + 8 + 2 + 8 + 2 + 32,
         seeds = [b"platform_config"],
         bump
     )]
@@ -552,6 +1374,57 @@ pub struct RemoveFromWhitelist<'info> {
     pub whitelist_entry: Account<'info, WhitelistEntry>,
 }
 
+#[derive(Accounts)]
+pub struct RegisterAffiliate<'info> {
+    #[account(
+        mut,
+        constraint = admin.key() == platform_config.admin @ CrowdfundingError::Unauthorized
+    )]
+    pub admin: Signer<'info>,
+    
+    #[account(
+        seeds = [b"platform_config"],
+        bump = platform_config.bump
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    
+    /// CHECK: Wallet to be registered as an affiliate
+    pub affiliate_wallet: AccountInfo<'info>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + 32 + 32 + 8 + 1 + 8 + 8 + 1 + 32,
+        seeds = [b"affiliate", affiliate_wallet.key().as_ref()],
+        bump
+    )]
+    pub affiliate: Account<'info, Affiliate>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DeactivateAffiliate<'info> {
+    #[account(
+        mut,
+        constraint = admin.key() == platform_config.admin @ CrowdfundingError::Unauthorized
+    )]
+    pub admin: Signer<'info>,
+    
+    #[account(
+        seeds = [b"platform_config"],
+        bump = platform_config.bump
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"affiliate", affiliate.wallet.as_ref()],
+        bump = affiliate.bump
+    )]
+    pub affiliate: Account<'info, Affiliate>,
+}
+
 #[derive(Accounts)]
 #[instruction(property_id: String)]
 pub struct CreateCampaign<'info> {
@@ -575,7 +1448,7 @@ pub struct CreateCampaign<'info> {
     #[account(
         init,
         payer = creator,
-        space = 8 + 32 + 32 + 32 + 4 + 64 + 8 + 8 + 2 + 8 + 8 + 8 + 8 + 4 + 1 + 8 + 1 + 1 + 64,
+        space = 8 + 32 + 32 + 32 + 4 + 64 + 8 + 8 + 2 + 8 + 8 + 8 + 8 + 4 + 1 + 8 + 1 + 1 + 8 + 1 + 8 + 1 + 8 + 2 + 8 + 8 + 1 + 64,
         seeds = [b"campaign", property_id.as_bytes(), creator.key().as_ref()],
         bump
     )]
@@ -589,8 +1462,28 @@ pub struct CreateCampaign<'info> {
     )]
     pub escrow_vault: AccountInfo<'info>,
     
-    /// The property token mint (campaign has mint authority)
-    #[account(mut)]
+    /// CHECK: PDA vault holding the creator's performance bond
+    #[account(
+        mut,
+        seeds = [b"bond", campaign.key().as_ref()],
+        bump
+    )]
+    pub bond_vault: AccountInfo<'info>,
+    
+    #[account(
+        init_if_needed,
+        payer = creator,
+        space = 8 + 4 + 64 + 32 + 32 + 32 + 32 + 1 + 8 + 1 + 32,
+        seeds = [b"property", property_registry_seed(&property_id).as_ref()],
+        bump
+    )]
+    pub property_registry: Account<'info, PropertyRegistry>,
+    
+    /// The property token mint; its mint authority must already be the campaign
+    #[account(
+        mut,
+        constraint = property_mint.mint_authority == COption::Some(campaign.key()) @ CrowdfundingError::InvalidMintAuthority
+    )]
     pub property_mint: Account<'info, Mint>,
     
     pub system_program: Program<'info, System>,
@@ -621,13 +1514,31 @@ pub struct Invest<'info> {
     #[account(
         init_if_needed,
         payer = investor,
-        space = 8 + 32 + 32 + 8 + 8 + 8 + 1 + 1 + 1 + 32,
+        space = 8 + 32 + 32 + 8 + 8 + 8 + 1 + 1 + 1 + 1 + 33 + 32,
         seeds = [b"investor", campaign.key().as_ref(), investor.key().as_ref()],
         bump
     )]
     pub investor_record: Account<'info, InvestorRecord>,
     
     pub system_program: Program<'info, System>,
+    
+    /// Optional affiliate who referred this investment
+    #[account(
+        mut,
+        seeds = [b"affiliate", referrer.wallet.as_ref()],
+        bump = referrer.bump
+    )]
+    pub referrer: Option<Account<'info, Affiliate>>,
+    
+    /// Per-campaign referral totals; given if and only if `referrer` is
+    #[account(
+        init_if_needed,
+        payer = investor,
+        space = 8 + 32 + 32 + 8 + 1 + 1 + 32,
+        seeds = [b"referral", campaign.key().as_ref(), referrer_wallet(&referrer).as_ref()],
+        bump
+    )]
+    pub referral_record: Option<Account<'info, ReferralRecord>>,
 }
 
 #[derive(Accounts)]
@@ -665,8 +1576,62 @@ pub struct FinalizeCampaign<'info> {
         constraint = platform_wallet.key() == platform_config.platform_wallet @ CrowdfundingError::InvalidPlatformWallet
     )]
     pub platform_wallet: AccountInfo<'info>,
+
+    /// Registry entry of the property; omitted only for campaigns created
+    /// before the property registry existed
+    #[account(
+        mut,
+        seeds = [b"property", property_registry_seed(&campaign.property_id).as_ref()],
+        bump = property_registry.bump,
+        constraint = property_registry.campaign == campaign.key() @ CrowdfundingError::InvalidPropertyRegistry
+    )]
+    pub property_registry: Option<Account<'info, PropertyRegistry>>,
+    
+    #[account(
+        constraint = property_mint.key() == campaign.property_mint @ CrowdfundingError::InvalidMint
+    )]
+    pub property_mint: Account<'info, Mint>,
+    
+    /// CHECK: Dividend pool PDA owned by `property_dividends`, created by CPI
+    #[account(
+        mut,
+        seeds = [b"dividend_pool", property_mint.key().as_ref()],
+        seeds::program = dividends_program.key(),
+        bump
+    )]
+    pub dividend_pool: UncheckedAccount<'info>,
+    
+    /// CHECK: Dividend vault PDA owned by `property_dividends`
+    #[account(
+        mut,
+        seeds = [b"dividend_vault", dividend_pool.key().as_ref()],
+        seeds::program = dividends_program.key(),
+        bump
+    )]
+    pub dividend_vault: UncheckedAccount<'info>,
     
+    /// CHECK: Tax vault PDA owned by `property_dividends`, funded by CPI
+    #[account(
+        mut,
+        seeds = [b"tax_vault", dividend_pool.key().as_ref()],
+        seeds::program = dividends_program.key(),
+        bump
+    )]
+    pub tax_vault: UncheckedAccount<'info>,
+    
+    /// CHECK: Reserve vault PDA owned by `property_dividends`, funded by CPI
+    #[account(
+        mut,
+        seeds = [b"reserve_vault", dividend_pool.key().as_ref()],
+        seeds::program = dividends_program.key(),
+        bump
+    )]
+    pub reserve_vault: UncheckedAccount<'info>,
+    
+    pub dividends_program: Program<'info, PropertyDividends>,
     pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
 }
 
 #[derive(Accounts)]
@@ -683,6 +1648,16 @@ pub struct CancelCampaign<'info> {
         bump = campaign.bump
     )]
     pub campaign: Account<'info, Campaign>,
+
+    /// Registry entry of the property; omitted only for campaigns created
+    /// before the property registry existed
+    #[account(
+        mut,
+        seeds = [b"property", property_registry_seed(&campaign.property_id).as_ref()],
+        bump = property_registry.bump,
+        constraint = property_registry.campaign == campaign.key() @ CrowdfundingError::InvalidPropertyRegistry
+    )]
+    pub property_registry: Option<Account<'info, PropertyRegistry>>,
 }
 
 #[derive(Accounts)]
@@ -715,6 +1690,28 @@ pub struct ClaimRefund<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct ProcessRefunds<'info> {
+    /// Anyone may run the crank
+    pub payer: Signer<'info>,
+    
+    #[account(
+        seeds = [b"campaign", campaign.property_id.as_bytes(), campaign.creator.as_ref()],
+        bump = campaign.bump
+    )]
+    pub campaign: Account<'info, Campaign>,
+    
+    /// CHECK: PDA escrow vault
+    #[account(
+        mut,
+        seeds = [b"escrow", campaign.key().as_ref()],
+        bump = campaign.escrow_bump
+    )]
+    pub escrow_vault: AccountInfo<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
 #[derive(Accounts)]
 pub struct ClaimTokens<'info> {
     #[account(mut)]
@@ -750,6 +1747,180 @@ pub struct ClaimTokens<'info> {
     pub token_program: Program<'info, Token>,
 }
 
+#[derive(Accounts)]
+pub struct ReleaseBond<'info> {
+    #[account(
+        mut,
+        constraint = creator.key() == campaign.creator @ CrowdfundingError::Unauthorized
+    )]
+    pub creator: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"campaign", campaign.property_id.as_bytes(), campaign.creator.as_ref()],
+        bump = campaign.bump
+    )]
+    pub campaign: Account<'info, Campaign>,
+    
+    /// CHECK: PDA bond vault
+    #[account(
+        mut,
+        seeds = [b"bond", campaign.key().as_ref()],
+        bump = campaign.bond_bump
+    )]
+    pub bond_vault: AccountInfo<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SlashBond<'info> {
+    #[account(
+        constraint = admin.key() == platform_config.admin @ CrowdfundingError::Unauthorized
+    )]
+    pub admin: Signer<'info>,
+    
+    #[account(
+        seeds = [b"platform_config"],
+        bump = platform_config.bump
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"campaign", campaign.property_id.as_bytes(), campaign.creator.as_ref()],
+        bump = campaign.bump
+    )]
+    pub campaign: Account<'info, Campaign>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimBondShare<'info> {
+    #[account(mut)]
+    pub investor: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"campaign", campaign.property_id.as_bytes(), campaign.creator.as_ref()],
+        bump = campaign.bump
+    )]
+    pub campaign: Account<'info, Campaign>,
+    
+    /// CHECK: PDA bond vault
+    #[account(
+        mut,
+        seeds = [b"bond", campaign.key().as_ref()],
+        bump = campaign.bond_bump
+    )]
+    pub bond_vault: AccountInfo<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"investor", campaign.key().as_ref(), investor.key().as_ref()],
+        bump = investor_record.bump,
+        constraint = investor_record.investor == investor.key() @ CrowdfundingError::Unauthorized
+    )]
+    pub investor_record: Account<'info, InvestorRecord>,
+    
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimReferralReward<'info> {
+    #[account(
+        mut,
+        constraint = affiliate_wallet.key() == affiliate.wallet @ CrowdfundingError::Unauthorized
+    )]
+    pub affiliate_wallet: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"affiliate", affiliate.wallet.as_ref()],
+        bump = affiliate.bump
+    )]
+    pub affiliate: Account<'info, Affiliate>,
+    
+    #[account(
+        mut,
+        seeds = [b"campaign", campaign.property_id.as_bytes(), campaign.creator.as_ref()],
+        bump = campaign.bump
+    )]
+    pub campaign: Account<'info, Campaign>,
+    
+    /// CHECK: PDA escrow vault
+    #[account(
+        mut,
+        seeds = [b"escrow", campaign.key().as_ref()],
+        bump = campaign.escrow_bump
+    )]
+    pub escrow_vault: AccountInfo<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"referral", campaign.key().as_ref(), affiliate.wallet.as_ref()],
+        bump = referral_record.bump
+    )]
+    pub referral_record: Account<'info, ReferralRecord>,
+    
+    /// CHECK: Campaign creator; gets the escrow's rent reserve back when it closes
+    #[account(
+        mut,
+        address = campaign.creator @ CrowdfundingError::Unauthorized
+    )]
+    pub creator: UncheckedAccount<'info>,
+    
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct ResizeInvestorRecord<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(
+        seeds = [b"campaign", campaign.property_id.as_bytes(), campaign.creator.as_ref()],
+        bump = campaign.bump
+    )]
+    pub campaign: Account<'info, Campaign>,
+    
+    #[account(
+        mut,
+        seeds = [b"investor", campaign.key().as_ref(), investor_record.investor.as_ref()],
+        bump = investor_record.bump,
+        realloc = 8 + 32 + 32 + 8 + 8 + 8 + 1 + 1 + 1 + 1 + 33 + 32,
+        realloc::payer = payer,
+        realloc::zero = false
+    )]
+    pub investor_record: Account<'info, InvestorRecord>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DistributeTokens<'info> {
+    /// Anyone may run the crank; pays rent for missing investor token accounts
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(
+        seeds = [b"campaign", campaign.property_id.as_bytes(), campaign.creator.as_ref()],
+        bump = campaign.bump
+    )]
+    pub campaign: Account<'info, Campaign>,
+    
+    #[account(
+        mut,
+        constraint = property_mint.key() == campaign.property_mint @ CrowdfundingError::InvalidMint
+    )]
+    pub property_mint: Account<'info, Mint>,
+    
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
 #[derive(Accounts)]
 pub struct UpdatePlatformConfig<'info> {
     #[account(
@@ -797,6 +1968,7 @@ pub struct CampaignCreated {
     pub platform_tokens: u64,
     pub tokens_available: u64,
     pub deadline: i64,
+    pub bond_amount: u64,
 }
 
 #[event]
@@ -806,6 +1978,7 @@ pub struct InvestmentMade {
     pub amount: u64,
     pub tokens_purchased: u64,
     pub total_invested: u64,
+    pub referrer: Option<Pubkey>,
 }
 
 #[event]
@@ -815,6 +1988,8 @@ pub struct CampaignFinalized {
     pub platform_share: u64,
     pub creator_share: u64,
     pub investors: u32,
+    pub referral_share: u64,
+    pub dividend_pool: Pubkey,
 }
 
 #[event]
@@ -831,6 +2006,14 @@ pub struct RefundClaimed {
     pub amount: u64,
 }
 
+#[event]
+pub struct RefundsProcessed {
+    pub campaign: Pubkey,
+    pub processed_by: Pubkey,
+    pub investors: u32,
+    pub amount: u64,
+}
+
 #[event]
 pub struct TokensClaimed {
     pub campaign: Pubkey,
@@ -838,12 +2021,73 @@ pub struct TokensClaimed {
     pub tokens: u64,
 }
 
+#[event]
+pub struct TokensDistributed {
+    pub campaign: Pubkey,
+    pub distributed_by: Pubkey,
+    pub investors: u32,
+    pub tokens: u64,
+}
+
 #[event]
 pub struct PlatformWalletUpdated {
     pub old_wallet: Pubkey,
     pub new_wallet: Pubkey,
 }
 
+#[event]
+pub struct AffiliateRegistered {
+    pub affiliate: Pubkey,
+    pub registered_by: Pubkey,
+}
+
+#[event]
+pub struct AffiliateDeactivated {
+    pub affiliate: Pubkey,
+}
+
+#[event]
+pub struct ReferralShareUpdated {
+    pub old_share_bps: u16,
+    pub new_share_bps: u16,
+}
+
+#[event]
+pub struct ReferralRewardClaimed {
+    pub campaign: Pubkey,
+    pub affiliate: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct BondConfigUpdated {
+    pub bond_lamports: u64,
+    pub bond_bps: u16,
+    pub bond_holding_period: i64,
+}
+
+#[event]
+pub struct BondReleased {
+    pub campaign: Pubkey,
+    pub creator: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct BondSlashed {
+    pub campaign: Pubkey,
+    pub creator: Pubkey,
+    pub amount: u64,
+    pub slashed_by: Pubkey,
+}
+
+#[event]
+pub struct BondShareClaimed {
+    pub campaign: Pubkey,
+    pub investor: Pubkey,
+    pub amount: u64,
+}
+
 // ============================================================================
 // Errors
 // ============================================================================
@@ -898,4 +2142,158 @@ pub enum CrowdfundingError {
     InvalidTokenOwner,
     #[msg("Invalid token mint")]
     InvalidMint,
+    #[msg("Invalid bond configuration")]
+    InvalidBondConfig,
+    #[msg("Bond is not held")]
+    BondNotHeld,
+    #[msg("Bond is still locked")]
+    BondLocked,
+    #[msg("Bond has not been slashed")]
+    BondNotSlashed,
+    #[msg("Bond share already claimed")]
+    BondShareAlreadyClaimed,
+    #[msg("Campaign has no investors to compensate")]
+    NoInvestorsToCompensate,
+    #[msg("Affiliate is not active")]
+    AffiliateNotActive,
+    #[msg("Investors cannot refer themselves")]
+    SelfReferral,
+    #[msg("Investment is already attributed to a different referrer")]
+    ReferrerMismatch,
+    #[msg("Referral record account is required with a referrer")]
+    MissingReferralRecord,
+    #[msg("Invalid referral share (max 100%)")]
+    InvalidReferralShare,
+    #[msg("Referral reward already claimed")]
+    ReferralRewardAlreadyClaimed,
+    #[msg("No referral reward to claim")]
+    NoReferralReward,
+    #[msg("Invalid remaining accounts")]
+    InvalidRemainingAccounts,
+    #[msg("Investor record does not belong to this campaign or investor")]
+    InvalidInvestorRecord,
+    #[msg("Property already has an active or funded campaign")]
+    PropertyAlreadyRegistered,
+    #[msg("Property registry does not belong to this campaign")]
+    InvalidPropertyRegistry,
+    #[msg("Bond is below the vault's rent-exempt minimum")]
+    BondBelowRentMinimum,
+    #[msg("Property registry account is required for this campaign")]
+    MissingPropertyRegistry,
+    #[msg("Existing dividend pool is not controlled by the creator or property manager")]
+    PoolAuthorityMismatch,
+    #[msg("Property mint authority must be the campaign")]
+    InvalidMintAuthority,
+    #[msg("Campaign is still active")]
+    CampaignStillActive,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RENT: u64 = 890_880;
+
+    #[test]
+    fn bond_shares_are_pro_rata_until_the_last_claim() {
+        // 1_000 bond (plus the vault's rent) over three equal investors
+        let (bond, total_raised) = (1_000, 300);
+        let mut vault = bond + RENT;
+        let mut claimed_invested = 0;
+        let mut paid = Vec::new();
+        for _ in 0..3 {
+            claimed_invested += 100;
+            let share = bond_share(bond, 100, total_raised, claimed_invested, vault, RENT).unwrap();
+            vault -= share;
+            paid.push(share);
+        }
+
+        // The last claimer takes the rounding remainder and the rent reserve
+        assert_eq!(paid, vec![333, 333, 334 + RENT]);
+        assert_eq!(vault, 0);
+    }
+
+    #[test]
+    fn bond_shares_never_dip_into_the_rent_reserve() {
+        // A vault short of its recorded bond only pays what is above rent
+        let share = bond_share(1_000, 50, 100, 50, RENT + 200, RENT).unwrap();
+        assert_eq!(share, 200);
+
+        let share = bond_share(1_000, 50, 100, 50, RENT - 1, RENT).unwrap();
+        assert_eq!(share, 0);
+    }
+
+    #[test]
+    fn bond_share_handles_large_amounts() {
+        let share = bond_share(u64::MAX, u64::MAX / 2, u64::MAX, u64::MAX / 2, u64::MAX, RENT).unwrap();
+        assert_eq!(share, u64::MAX / 2);
+    }
+
+    #[test]
+    fn referral_reward_is_a_share_of_the_platform_cut() {
+        // 20% of the platform's 5% on 1_000_000 lamports
+        assert_eq!(referral_reward(1_000_000, 500, 2_000).unwrap(), 10_000);
+        assert_eq!(referral_reward(1_000_000, 500, 10_000).unwrap(), 50_000);
+        assert_eq!(referral_reward(1_000_000, 500, 0).unwrap(), 0);
+        assert_eq!(referral_reward(1_000_000, 0, 2_000).unwrap(), 0);
+    }
+
+    #[test]
+    fn referral_reward_rounds_down() {
+        // 1% of 1% of 9_999 is 0.9999
+        assert_eq!(referral_reward(9_999, 100, 100).unwrap(), 0);
+        assert_eq!(referral_reward(10_001, 100, 100).unwrap(), 1);
+    }
+
+    #[test]
+    fn referral_reward_handles_large_amounts() {
+        assert_eq!(referral_reward(u64::MAX, 10_000, 10_000).unwrap(), u64::MAX);
+    }
+
+    #[test]
+    fn tokens_are_due_once() {
+        let mut record = InvestorRecord {
+            tokens_purchased: 250,
+            ..Default::default()
+        };
+        assert_eq!(record.tokens_due(), 250);
+
+        record.tokens_claimed = true;
+        assert_eq!(record.tokens_due(), 0);
+        assert_eq!(InvestorRecord::default().tokens_due(), 0);
+    }
+
+    #[test]
+    fn refunds_are_due_once() {
+        let mut record = InvestorRecord {
+            amount_invested: 5_000,
+            tokens_purchased: 250,
+            ..Default::default()
+        };
+        assert_eq!(record.refund_due(), 5_000);
+
+        record.refunded = true;
+        assert_eq!(record.refund_due(), 0);
+        assert_eq!(InvestorRecord::default().refund_due(), 0);
+    }
+
+    #[test]
+    fn property_ids_normalize_case_and_whitespace() {
+        assert_eq!(normalize_property_id("  PROP-42 Main St\t"), "prop-42 main st");
+        assert_eq!(
+            property_registry_seed("Prop-42 Main St"),
+            property_registry_seed(" prop-42 MAIN ST ")
+        );
+        assert_ne!(property_registry_seed("prop-42"), property_registry_seed("prop-43"));
+        // Inner whitespace is part of the identifier
+        assert_ne!(property_registry_seed("prop 42"), property_registry_seed("prop42"));
+    }
+
+    #[test]
+    fn long_property_ids_fit_in_one_seed() {
+        // Raw 64-byte identifiers would exceed the 32-byte seed limit
+        let seed = property_registry_seed(&"x".repeat(64));
+        assert!(Pubkey::try_find_program_address(&[b"property", &seed], &crate::ID).is_some());
+        assert_ne!(seed, property_registry_seed(&"x".repeat(63)));
+    }
 }
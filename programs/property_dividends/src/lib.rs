@@ -1,23 +1,87 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_lang::Discriminator;
+use anchor_spl::associated_token::{get_associated_token_address, AssociatedToken};
 use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use dividend_merkle::{amount_leaf, balance_leaf, verify_proof};
 
 declare_id!("78JohfdVhYCV6EPfC3baRkNoWUA5yDmw5HXkroeiSpCP");
 
+/// Fixed-point scale of `DistributionRecord.amount_per_token`
+pub const AMOUNT_PER_TOKEN_SCALE: u128 = 1_000_000_000_000_000_000;
+
+/// Length of a day for `distribution_frequency_days`
+pub const SECONDS_PER_DAY: i64 = 86_400;
+
+/// Seconds a preferred return rate applies over
+pub const SECONDS_PER_YEAR: i64 = 365 * SECONDS_PER_DAY;
+
+/// Claim period of new pools' distributions, until changed with `set_unclaimed_policy`
+pub const DEFAULT_CLAIM_PERIOD_DAYS: u64 = 365;
+
+/// Largest holder set a merkle-amount distribution's claimed bitmap can track
+pub const MAX_MERKLE_HOLDERS: u32 = 80_000;
+
+/// Most `Exclude` accounts a pool can register; the crank passes all of them
+pub const MAX_EXCLUDED_ACCOUNTS: u32 = 16;
+
+/// Crowdfunding program whose campaigns issue property mints
+pub mod crowdfunding_program {
+    use super::*;
+    declare_id!("2ghvWvTvMHdACLuGztbjERbvMPwwQcFTag6a6eU7RifY");
+}
+
 #[program]
 pub mod property_dividends {
     use super::*;
 
-    /// Initialize a dividend pool for a tokenized property
+    /// Initialize a dividend pool for a tokenized property. The authority must be
+    /// the mint authority, the creator of the finalized crowdfunding campaign for
+    /// the mint (pass `campaign`), or the platform admin (pass `platform_config`).
+    ///
+    /// Pools pay out SOL unless `payout_mint` is given, in which case deposits and
+    /// claims move that token through `token_vault`. A property can have one pool
+    /// of each kind. SOL pools fund `dividend_vault`, `tax_vault` and
+    /// `reserve_vault` to the rent-exempt minimum. The vaults can then take amounts
+    /// of any size and pay out everything owed from them, dust included.
     pub fn initialize_pool(
         ctx: Context<InitializePool>,
         property_id: String,
         distribution_frequency_days: u64,
     ) -> Result<()> {
-        let pool = &mut ctx.accounts.dividend_pool;
-        
         require!(property_id.len() <= 64, DividendError::PropertyIdTooLong);
         require!(distribution_frequency_days > 0, DividendError::InvalidFrequency);
         
+        let authority = ctx.accounts.authority.key();
+        let is_issuer = ctx.accounts.property_mint.mint_authority == COption::Some(authority)
+            || match ctx.accounts.campaign.as_ref() {
+                Some(campaign_info) => {
+                    let campaign = load_crowdfunding_account::<CrowdfundingCampaign>(campaign_info, "Campaign")?;
+                    campaign.creator == authority
+                        && campaign.property_mint == ctx.accounts.property_mint.key()
+                        && campaign.status == CROWDFUNDING_STATUS_FUNDED
+                        && ctx.accounts.property_mint.mint_authority == COption::Some(campaign_info.key())
+                }
+                None => false,
+            }
+            || match ctx.accounts.platform_config.as_ref() {
+                Some(config) => load_platform_admin(config)? == authority,
+                None => false,
+            };
+        require!(is_issuer, DividendError::NotPropertyIssuer);
+        
+        let payout_mint = ctx.accounts.payout_mint.as_ref().map(|mint| mint.key());
+        require!(
+            payout_mint.is_some() == ctx.accounts.token_vault.is_some(),
+            DividendError::InvalidPayoutAccounts
+        );
+        require!(
+            payout_mint != Some(ctx.accounts.property_mint.key()),
+            DividendError::InvalidMint
+        );
+        
+        let pool = &mut ctx.accounts.dividend_pool;
+        
         pool.authority = ctx.accounts.authority.key();
         pool.property_mint = ctx.accounts.property_mint.key();
         pool.dividend_vault = ctx.accounts.dividend_vault.key();
@@ -28,304 +92,5219 @@ pub mod property_dividends {
         pool.last_distribution_time = 0;
         pool.total_deposited_current_epoch = 0;
         pool.bump = ctx.bumps.dividend_pool;
+        pool.payout_mint = payout_mint;
+        pool.claim_period_days = DEFAULT_CLAIM_PERIOD_DAYS;
+        pool.unclaimed_policy = UnclaimedPolicy::Rollover;
+        pool.treasury = Pubkey::default();
+        pool.compliance_authority = pool.authority;
+        pool.management_fee_bps = 0;
+        pool.reserve_bps = 0;
+        pool.manager = pool.authority;
+        pool.reserve_balance = 0;
+        pool.deductible_deposits = 0;
+        
+        if payout_mint.is_none() {
+            for vault in [
+                &ctx.accounts.dividend_vault,
+                &ctx.accounts.tax_vault,
+                &ctx.accounts.reserve_vault,
+            ] {
+                fund_rent_exempt(&ctx.accounts.authority, vault, &ctx.accounts.system_program)?;
+            }
+        }
         
         emit!(PoolInitialized {
             pool: pool.key(),
             property_mint: pool.property_mint,
             authority: pool.authority,
+            payout_mint,
         });
         
         Ok(())
     }
 
-    /// Deposit rental income/dividends into the pool (called by property manager)
+    /// Deposit rental income/dividends into the pool (called by property manager).
+    /// Streaming pools stream the deposit to checkpointed holders (pass `stream_state`).
+    /// The pool authority and any active depositor on the allow-list may deposit.
+    /// Listed depositors pass their `Depositor` record, which tracks their totals.
     pub fn deposit_dividend(ctx: Context<DepositDividend>, amount: u64) -> Result<()> {
         require!(amount > 0, DividendError::InvalidAmount);
         
         let pool = &mut ctx.accounts.dividend_pool;
         
-        // Transfer SOL from authority to vault
-        let cpi_context = CpiContext::new(
-            ctx.accounts.system_program.to_account_info(),
-            anchor_lang::system_program::Transfer {
-                from: ctx.accounts.authority.to_account_info(),
-                to: ctx.accounts.dividend_vault.to_account_info(),
-            },
-        );
-        anchor_lang::system_program::transfer(cpi_context, amount)?;
+        if pool.payout_mint.is_some() {
+            // Transfer payout tokens from the depositor's account to the token vault
+            let (source, vault, token_program) = match (
+                &ctx.accounts.depositor_token_account,
+                &ctx.accounts.token_vault,
+                &ctx.accounts.token_program,
+            ) {
+                (Some(source), Some(vault), Some(token_program)) => (source, vault, token_program),
+                _ => return err!(DividendError::InvalidPayoutAccounts),
+            };
+            token::transfer(
+                CpiContext::new(
+                    token_program.to_account_info(),
+                    Transfer {
+                        from: source.to_account_info(),
+                        to: vault.to_account_info(),
+                        authority: ctx.accounts.depositor.to_account_info(),
+                    },
+                ),
+                amount,
+            )?;
+        } else {
+            // Transfer SOL from depositor to vault
+            let cpi_context = CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.depositor.to_account_info(),
+                    to: ctx.accounts.dividend_vault.to_account_info(),
+                },
+            );
+            anchor_lang::system_program::transfer(cpi_context, amount)?;
+        }
         
-        pool.total_deposited_current_epoch = pool
-            .total_deposited_current_epoch
-            .checked_add(amount)
-            .ok_or(DividendError::Overflow)?;
+        if pool.streaming {
+            let stream = ctx
+                .accounts
+                .stream_state
+                .as_mut()
+                .ok_or(DividendError::WrongPoolMode)?;
+            stream.fund(amount, Clock::get()?.unix_timestamp)?;
+            
+            emit!(StreamFunded {
+                pool: pool.key(),
+                amount,
+                reward_rate: stream.reward_rate,
+                period_finish: stream.period_finish,
+            });
+        } else {
+            pool.record_deposit(amount)?;
+        }
+        
+        if let Some(record) = ctx.accounts.depositor_record.as_mut() {
+            record.total_deposited = record
+                .total_deposited
+                .checked_add(amount)
+                .ok_or(DividendError::Overflow)?;
+            record.deposit_count = record
+                .deposit_count
+                .checked_add(1)
+                .ok_or(DividendError::Overflow)?;
+            record.last_deposit_at = Clock::get()?.unix_timestamp;
+        }
         
         emit!(DividendDeposited {
             pool: pool.key(),
             amount,
             epoch: pool.current_epoch,
-            depositor: ctx.accounts.authority.key(),
+            depositor: ctx.accounts.depositor.key(),
         });
         
         Ok(())
     }
 
-    /// Start a new distribution epoch (snapshot token holdings)
-    pub fn start_distribution(ctx: Context<StartDistribution>) -> Result<()> {
-        let pool = &mut ctx.accounts.dividend_pool;
+    /// Start a new distribution epoch. Entitlements are fixed by `snapshot_root`, the
+    /// merkle root of every holder's balance at `snapshot_slot` (see
+    /// `dividend_merkle::balance_leaf`). At most one distribution opens per
+    /// `distribution_frequency_days` unless `override_interval` is set for a special
+    /// distribution.
+    ///
+    /// `snapshot_total` is the sum of the snapshot's balances, the
+    /// `BalanceSnapshot::total_balance` of the tree behind `snapshot_root`, and is
+    /// the per-token denominator. `Exclude` accounts are left out of the snapshot.
+    ///
+    /// The management fee and reserve are taken from deposits made since the last
+    /// distribution and paid to the manager and the reserve vault. A pool with a
+    /// waterfall then sets aside the preferred class's allocation; pass its
+    /// `Waterfall`.
+    pub fn start_distribution(
+        ctx: Context<StartDistribution>,
+        snapshot_slot: u64,
+        snapshot_root: [u8; 32],
+        snapshot_total: u64,
+        override_interval: bool,
+    ) -> Result<()> {
         let clock = Clock::get()?;
-        
         require!(
-            pool.total_deposited_current_epoch > 0,
-            DividendError::NoDividendsToDistribute
+            override_interval || ctx.accounts.dividend_pool.distribution_due(clock.unix_timestamp)?,
+            DividendError::DistributionNotDue
         );
+        require!(snapshot_slot <= clock.slot, DividendError::InvalidSnapshot);
+        require!(snapshot_root != [0u8; 32], DividendError::InvalidSnapshot);
         
-        // Get total supply of property tokens
-        let total_supply = ctx.accounts.property_mint.supply;
-        require!(total_supply > 0, DividendError::NoTokensInCirculation);
+        // Supply only grows once minted, so no snapshot can hold more than it
+        let mint_supply = ctx.accounts.property_mint.supply;
+        require!(snapshot_total <= mint_supply, DividendError::InvalidSnapshot);
         
-        // Create distribution record
         let distribution = &mut ctx.accounts.distribution_record;
-        distribution.pool = pool.key();
-        distribution.epoch = pool.current_epoch;
-        distribution.total_amount = pool.total_deposited_current_epoch;
-        distribution.total_token_supply = total_supply;
-        distribution.amount_per_token = pool
-            .total_deposited_current_epoch
-            .checked_div(total_supply)
-            .ok_or(DividendError::Overflow)?;
-        distribution.distributed_at = clock.unix_timestamp;
-        distribution.total_claimed = 0;
         distribution.bump = ctx.bumps.distribution_record;
+        distribution.snapshot_slot = snapshot_slot;
+        distribution.snapshot_root = snapshot_root;
+        let deductions = open_distribution(
+            &mut ctx.accounts.dividend_pool,
+            distribution,
+            ctx.accounts.waterfall.as_mut(),
+            snapshot_total,
+            mint_supply - snapshot_total,
+            ctx.accounts.authority.key(),
+        )?;
         
-        // Update pool state
-        pool.total_distributed = pool
-            .total_distributed
-            .checked_add(pool.total_deposited_current_epoch)
+        pay_deductions(
+            &ctx.accounts.dividend_pool,
+            &deductions,
+            DeductionPayees {
+                dividend_vault: &ctx.accounts.dividend_vault,
+                vault_bump: ctx.bumps.dividend_vault,
+                manager: &ctx.accounts.manager,
+                reserve_vault: &ctx.accounts.reserve_vault,
+                token_vault: ctx.accounts.token_vault.as_ref(),
+                manager_token_account: ctx.accounts.manager_token_account.as_ref(),
+                reserve_token_vault: ctx.accounts.reserve_token_vault.as_ref(),
+                token_program: ctx.accounts.token_program.as_ref(),
+                system_program: &ctx.accounts.system_program,
+            },
+        )
+    }
+
+    /// Permissionless crank: open the next distribution once the pool's interval has
+    /// elapsed and it holds undistributed deposits. Holder balances are snapshotted at
+    /// the current slot; the pool authority posts the snapshot root with
+    /// `post_snapshot_root` before claims open.
+    ///
+    /// The denominator is the supply at the snapshot slot less the pool's `Exclude`
+    /// accounts, which `remaining_accounts` must list as `[excluded_account,
+    /// token_account]` pairs ordered by `excluded_account` address. The posted
+    /// snapshot's total has to match it.
+    pub fn crank_distribution<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CrankDistribution<'info>>,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        require!(
+            ctx.accounts.dividend_pool.distribution_due(clock.unix_timestamp)?,
+            DividendError::DistributionNotDue
+        );
+        
+        // Balances are read in the snapshot slot itself, so they are the snapshot's
+        let excluded_supply = excluded_supply(&ctx.accounts.dividend_pool, ctx.remaining_accounts)?;
+        let circulating_supply = ctx
+            .accounts
+            .property_mint
+            .supply
+            .checked_sub(excluded_supply)
             .ok_or(DividendError::Overflow)?;
-        pool.last_distribution_time = clock.unix_timestamp;
-        pool.current_epoch = pool.current_epoch.checked_add(1).ok_or(DividendError::Overflow)?;
-        pool.total_deposited_current_epoch = 0;
+        let distribution = &mut ctx.accounts.distribution_record;
+        distribution.bump = ctx.bumps.distribution_record;
+        distribution.snapshot_slot = clock.slot;
+        distribution.snapshot_root = [0u8; 32];
+        let deductions = open_distribution(
+            &mut ctx.accounts.dividend_pool,
+            distribution,
+            ctx.accounts.waterfall.as_mut(),
+            circulating_supply,
+            excluded_supply,
+            ctx.accounts.cranker.key(),
+        )?;
         
-        emit!(DistributionStarted {
-            pool: pool.key(),
-            epoch: distribution.epoch,
-            total_amount: distribution.total_amount,
-            amount_per_token: distribution.amount_per_token,
+        pay_deductions(
+            &ctx.accounts.dividend_pool,
+            &deductions,
+            DeductionPayees {
+                dividend_vault: &ctx.accounts.dividend_vault,
+                vault_bump: ctx.bumps.dividend_vault,
+                manager: &ctx.accounts.manager,
+                reserve_vault: &ctx.accounts.reserve_vault,
+                token_vault: ctx.accounts.token_vault.as_ref(),
+                manager_token_account: ctx.accounts.manager_token_account.as_ref(),
+                reserve_token_vault: ctx.accounts.reserve_token_vault.as_ref(),
+                token_program: ctx.accounts.token_program.as_ref(),
+                system_program: &ctx.accounts.system_program,
+            },
+        )
+    }
+
+    /// Post the balance snapshot root of a distribution opened by the crank or
+    /// migrated from the legacy layout. `snapshot_total`, the snapshot's
+    /// `BalanceSnapshot::total_balance`, must equal the circulating supply the
+    /// distribution was opened with.
+    pub fn post_snapshot_root(
+        ctx: Context<PostSnapshotRoot>,
+        epoch: u64,
+        snapshot_root: [u8; 32],
+        snapshot_total: u64,
+    ) -> Result<()> {
+        require!(snapshot_root != [0u8; 32], DividendError::InvalidSnapshot);
+        
+        let distribution = &mut ctx.accounts.distribution_record;
+        require!(
+            distribution.mode == DistributionMode::BalanceSnapshot,
+            DividendError::WrongDistributionMode
+        );
+        require!(
+            distribution.snapshot_root == [0u8; 32],
+            DividendError::SnapshotAlreadyPosted
+        );
+        require!(
+            snapshot_total == distribution.total_token_supply,
+            DividendError::SnapshotTotalMismatch
+        );
+        distribution.snapshot_root = snapshot_root;
+        
+        emit!(SnapshotRootPosted {
+            pool: ctx.accounts.dividend_pool.key(),
+            epoch,
+            snapshot_slot: distribution.snapshot_slot,
+            snapshot_root,
         });
         
         Ok(())
     }
 
-    /// Claim dividends for a specific epoch
-    pub fn claim_dividend(ctx: Context<ClaimDividend>, epoch: u64) -> Result<()> {
-        require!(!ctx.accounts.claim_record.claimed, DividendError::AlreadyClaimed);
+    /// Claim dividends for a specific epoch against `holder`'s snapshot balance,
+    /// proven by `proof` against the distribution's snapshot root. `holder` is the
+    /// signer, the owner of a redirected account whose beneficiary is the signer, or
+    /// a holder whose payout preference names the signer as delegate. The claim pays
+    /// the holder's payout address if they set one, and the signer otherwise.
+    /// A claim of the epoch at the holder's range-claim cursor moves the cursor on
+    /// instead of creating a `ClaimRecord`.
+    pub fn claim_dividend(
+        ctx: Context<ClaimDividend>,
+        epoch: u64,
+        snapshot_balance: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        require!(ctx.accounts.claim_record.data_is_empty(), DividendError::AlreadyClaimed);
 
-        let user_token_balance = ctx.accounts.user_token_account.amount;
-        require!(user_token_balance > 0, DividendError::NoTokensHeld);
+        let holder = ctx.accounts.holder.key();
+        let user = ctx.accounts.user.key();
+        let preference = ctx.accounts.payout_preference.as_ref();
+        authorize_claim(holder, user, ctx.accounts.redirect.as_ref(), preference)?;
+        let recipient = payout_recipient(user, preference);
+        let recipient_account = payout_account(
+            &ctx.accounts.user,
+            ctx.accounts.payout_address.as_ref(),
+            recipient,
+        )?;
+        
+        // Epochs covered by the holder's cursor were settled by `claim_dividends_range`
+        require!(
+            !cursor_settles(&ctx.accounts.holder_cursor, epoch)?,
+            DividendError::AlreadyClaimed
+        );
 
         let distribution = &mut ctx.accounts.distribution_record;
-        let raw = user_token_balance
-            .checked_mul(distribution.amount_per_token)
-            .ok_or(DividendError::Overflow)?;
-        let remaining = distribution
-            .total_amount
-            .saturating_sub(distribution.total_claimed);
-        let dividend_amount = raw.min(remaining);
-        require!(dividend_amount > 0, DividendError::NoDividendsToClaim);
+        let dividend_amount = snapshot_claim_amount(distribution, &holder, snapshot_balance, &proof)?;
+        let withheld = bps_of(dividend_amount, withholding_bps(&ctx.accounts.tax_profile)?)?;
+        let net = dividend_amount - withheld;
 
         let pool = &ctx.accounts.dividend_pool;
-        let pool_key = pool.key();
+        let token_payout = TokenPayout::for_pool(
+            pool,
+            ctx.accounts.token_vault.as_ref(),
+            ctx.accounts.recipient_token_account.as_ref(),
+            ctx.accounts.token_program.as_ref(),
+        )?;
+        pay_from_vault(
+            pool.key(),
+            &ctx.accounts.dividend_vault,
+            ctx.bumps.dividend_vault,
+            &recipient_account,
+            token_payout,
+            &ctx.accounts.system_program,
+            net,
+        )?;
+        if withheld > 0 {
+            let tax_payout = TokenPayout::for_pool(
+                pool,
+                ctx.accounts.token_vault.as_ref(),
+                ctx.accounts.tax_token_vault.as_ref(),
+                ctx.accounts.token_program.as_ref(),
+            )?;
+            pay_from_vault(
+                pool.key(),
+                &ctx.accounts.dividend_vault,
+                ctx.bumps.dividend_vault,
+                &ctx.accounts.tax_vault,
+                tax_payout,
+                &ctx.accounts.system_program,
+                withheld,
+            )?;
+        }
+
+        distribution.total_claimed = distribution
+            .total_claimed
+            .checked_add(dividend_amount)
+            .ok_or(DividendError::Overflow)?;
+        if !settle_on_cursor(&ctx.accounts.holder_cursor, distribution, epoch, snapshot_balance, dividend_amount)? {
+            distribution.claim_count = distribution
+                .claim_count
+                .checked_add(1)
+                .ok_or(DividendError::Overflow)?;
+            let distribution_key = distribution.key();
+            create_claim_record(
+                &ctx.accounts.user,
+                &ctx.accounts.claim_record,
+                &[b"claim", distribution_key.as_ref(), holder.as_ref(), &[ctx.bumps.claim_record]],
+                &ctx.accounts.system_program,
+                &ClaimRecord {
+                    user: holder,
+                    distribution: distribution_key,
+                    epoch,
+                    amount_claimed: dividend_amount,
+                    claimed_at: Clock::get()?.unix_timestamp,
+                    claimed: true,
+                    bump: ctx.bumps.claim_record,
+                    reinvested_amount: 0,
+                    tokens_reinvested: 0,
+                    withheld_amount: withheld,
+                    net_amount: net,
+                },
+            )?;
+        }
+
+        emit!(DividendClaimed {
+            pool: pool.key(),
+            user: holder,
+            recipient,
+            epoch,
+            amount: dividend_amount,
+            withheld,
+            net,
+        });
+
+        Ok(())
+    }
+
+    /// Claim a SOL dividend for an epoch and spend it on property tokens from a
+    /// marketplace listing, as long as the listing's price is at most
+    /// `max_price_per_token`. Tax is withheld first, and the net amount buys tokens
+    /// for the holder, who also gets any lamports left over after buying whole
+    /// tokens. The pool vault's associated token account for the property mint must
+    /// already exist.
+    pub fn reinvest_dividend(
+        ctx: Context<ReinvestDividend>,
+        epoch: u64,
+        snapshot_balance: u64,
+        proof: Vec<[u8; 32]>,
+        max_price_per_token: u64,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.dividend_pool.payout_mint.is_none(),
+            DividendError::WrongPoolMode
+        );
+        
+        let holder = ctx.accounts.user.key();
+        require!(
+            ctx.accounts.claim_record.data_is_empty()
+                && !cursor_settles(&ctx.accounts.holder_cursor, epoch)?,
+            DividendError::AlreadyClaimed
+        );
+        
+        let distribution = &mut ctx.accounts.distribution_record;
+        let dividend_amount = snapshot_claim_amount(distribution, &holder, snapshot_balance, &proof)?;
+        let withheld = bps_of(dividend_amount, withholding_bps(&ctx.accounts.tax_profile)?)?;
+        let net = dividend_amount - withheld;
+        
+        let listing = &ctx.accounts.listing;
+        let price = listing.price_per_token;
+        require!(
+            price > 0 && price <= max_price_per_token,
+            DividendError::PriceTooHigh
+        );
+        let tokens = (net / price).min(listing.amount);
+        require!(tokens > 0, DividendError::ReinvestmentTooSmall);
+        let spent = tokens.checked_mul(price).ok_or(DividendError::Overflow)?;
+        let refund = net - spent;
+        
+        let pool_key = ctx.accounts.dividend_pool.key();
         let seeds = &[
             b"dividend_vault",
             pool_key.as_ref(),
             &[ctx.bumps.dividend_vault],
         ];
         let signer_seeds = &[&seeds[..]];
-
-        let transfer_ix = anchor_lang::system_program::Transfer {
-            from: ctx.accounts.dividend_vault.to_account_info(),
-            to: ctx.accounts.user.to_account_info(),
-        };
-
-        anchor_lang::system_program::transfer(
+        
+        // The vault buys the tokens, then hands them to the holder
+        marketplace::cpi::buy_tokens(
             CpiContext::new_with_signer(
-                ctx.accounts.system_program.to_account_info(),
-                transfer_ix,
+                ctx.accounts.marketplace_program.to_account_info(),
+                marketplace::cpi::accounts::BuyTokens {
+                    buyer: ctx.accounts.dividend_vault.to_account_info(),
+                    seller: ctx.accounts.seller.to_account_info(),
+                    platform_wallet: ctx.accounts.platform_wallet.to_account_info(),
+                    marketplace: ctx.accounts.marketplace.to_account_info(),
+                    token_mint: ctx.accounts.property_mint.to_account_info(),
+                    listing: ctx.accounts.listing.to_account_info(),
+                    escrow_token_account: ctx.accounts.escrow_token_account.to_account_info(),
+                    buyer_token_account: ctx.accounts.vault_token_account.to_account_info(),
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                    associated_token_program: ctx.accounts.associated_token_program.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                },
                 signer_seeds,
             ),
-            dividend_amount,
+            tokens,
         )?;
-
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.vault_token_account.to_account_info(),
+                    to: ctx.accounts.holder_token_account.to_account_info(),
+                    authority: ctx.accounts.dividend_vault.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            tokens,
+        )?;
+        
+        if refund > 0 {
+            pay_from_vault(
+                pool_key,
+                &ctx.accounts.dividend_vault,
+                ctx.bumps.dividend_vault,
+                &ctx.accounts.user,
+                None,
+                &ctx.accounts.system_program,
+                refund,
+            )?;
+        }
+        if withheld > 0 {
+            pay_from_vault(
+                pool_key,
+                &ctx.accounts.dividend_vault,
+                ctx.bumps.dividend_vault,
+                &ctx.accounts.tax_vault,
+                None,
+                &ctx.accounts.system_program,
+                withheld,
+            )?;
+        }
+        
         distribution.total_claimed = distribution
             .total_claimed
             .checked_add(dividend_amount)
             .ok_or(DividendError::Overflow)?;
-
-        let claim_record = &mut ctx.accounts.claim_record;
-        claim_record.user = ctx.accounts.user.key();
-        claim_record.distribution = distribution.key();
-        claim_record.epoch = epoch;
-        claim_record.amount_claimed = dividend_amount;
-        claim_record.claimed_at = Clock::get()?.unix_timestamp;
-        claim_record.claimed = true;
-        claim_record.bump = ctx.bumps.claim_record;
-
-        emit!(DividendClaimed {
-            pool: pool.key(),
-            user: ctx.accounts.user.key(),
+        if !settle_on_cursor(&ctx.accounts.holder_cursor, distribution, epoch, snapshot_balance, dividend_amount)? {
+            distribution.claim_count = distribution
+                .claim_count
+                .checked_add(1)
+                .ok_or(DividendError::Overflow)?;
+            let distribution_key = distribution.key();
+            create_claim_record(
+                &ctx.accounts.user,
+                &ctx.accounts.claim_record,
+                &[b"claim", distribution_key.as_ref(), holder.as_ref(), &[ctx.bumps.claim_record]],
+                &ctx.accounts.system_program,
+                &ClaimRecord {
+                    user: holder,
+                    distribution: distribution_key,
+                    epoch,
+                    amount_claimed: dividend_amount,
+                    claimed_at: Clock::get()?.unix_timestamp,
+                    claimed: true,
+                    bump: ctx.bumps.claim_record,
+                    reinvested_amount: spent,
+                    tokens_reinvested: tokens,
+                    withheld_amount: withheld,
+                    net_amount: net,
+                },
+            )?;
+        }
+        
+        emit!(DividendReinvested {
+            pool: pool_key,
+            user: holder,
             epoch,
             amount: dividend_amount,
+            listing: ctx.accounts.listing.key(),
+            withheld,
+            tokens,
+            spent,
+            refunded: refund,
         });
-
+        
         Ok(())
     }
 
-    /// Get claimable dividend amount for a user (view function)
-    pub fn get_claimable_amount(ctx: Context<GetClaimableAmount>, _epoch: u64) -> Result<u64> {
-        let distribution = &ctx.accounts.distribution_record;
-        let user_token_balance = ctx.accounts.user_token_account.amount;
-
-        if user_token_balance == 0 {
-            return Ok(0);
+    /// Claim every balance-snapshot epoch from `from_epoch` onwards in one call. Pass
+    /// one `EpochClaim` per epoch and, in `remaining_accounts`, a
+    /// `[distribution_record, claim_record]` pair per epoch, where `claim_record` is
+    /// the holder's `ClaimRecord` address for that epoch (usually uninitialized).
+    ///
+    /// The holder's first range claim may start at any epoch; earlier epochs stay
+    /// claimable with `claim_dividend`. Later ones start at the cursor. Epochs
+    /// already claimed with `claim_dividend` and voided epochs are passed over. The
+    /// range stops at the first epoch it cannot pay in full: an expired,
+    /// merkle-amount, or unposted epoch, or one with a zero balance. The cursor
+    /// moves up to that epoch, which is claimed on its own or passed by the holder
+    /// with `skip_epochs`. A range claim never forfeits anything, so delegates and
+    /// redirect beneficiaries can run it too.
+    pub fn claim_dividends_range<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ClaimDividendsRange<'info>>,
+        from_epoch: u64,
+        claims: Vec<EpochClaim>,
+    ) -> Result<()> {
+        let holder = ctx.accounts.holder.key();
+        let user = ctx.accounts.user.key();
+        let preference = ctx.accounts.payout_preference.as_ref();
+        authorize_claim(holder, user, ctx.accounts.redirect.as_ref(), preference)?;
+        let recipient = payout_recipient(user, preference);
+        let recipient_account = payout_account(
+            &ctx.accounts.user,
+            ctx.accounts.payout_address.as_ref(),
+            recipient,
+        )?;
+        
+        let pool = &ctx.accounts.dividend_pool;
+        let pool_key = pool.key();
+        let cursor = &mut ctx.accounts.holder_cursor;
+        if cursor.holder == Pubkey::default() {
+            cursor.first_epoch = from_epoch;
+            cursor.next_epoch = from_epoch;
+        }
+        require!(from_epoch == cursor.next_epoch, DividendError::InvalidClaimRange);
+        require!(
+            !claims.is_empty() && claims.len() * 2 == ctx.remaining_accounts.len(),
+            DividendError::InvalidClaimRange
+        );
+        let end_epoch = from_epoch
+            .checked_add(claims.len() as u64)
+            .ok_or(DividendError::Overflow)?;
+        require!(end_epoch <= pool.current_epoch, DividendError::InvalidClaimRange);
+        
+        let now = Clock::get()?.unix_timestamp;
+        let bps = withholding_bps(&ctx.accounts.tax_profile)?;
+        let mut total: u64 = 0;
+        let mut total_withheld: u64 = 0;
+        let mut next_epoch = from_epoch;
+        let epochs = (from_epoch..end_epoch).zip(&claims);
+        for ((epoch, claim), pair) in epochs.zip(ctx.remaining_accounts.chunks_exact(2)) {
+            let mut distribution = load_epoch_pair(pair, pool_key, epoch, &holder)?;
+            if !pair[1].data_is_empty() || distribution.voided {
+                next_epoch = epoch + 1;
+                continue;
+            }
+            if distribution.mode != DistributionMode::BalanceSnapshot
+                || distribution.snapshot_root == [0u8; 32]
+                || distribution.is_expired(now)
+                || claim.snapshot_balance == 0
+            {
+                break;
+            }
+            require!(
+                verify_proof(
+                    &distribution.snapshot_root,
+                    balance_leaf(&holder, claim.snapshot_balance),
+                    &claim.proof,
+                ),
+                DividendError::InvalidSnapshotProof
+            );
+            
+            // Epochs are only ever settled in full, so the cursor records exactly
+            // the entitlement of each epoch it passes
+            let amount = entitlement(claim.snapshot_balance, distribution.amount_per_token)?;
+            let remaining = distribution
+                .total_amount
+                .saturating_sub(distribution.total_claimed);
+            if amount > remaining {
+                break;
+            }
+            next_epoch = epoch + 1;
+            if amount == 0 {
+                continue;
+            }
+            
+            distribution.total_claimed = distribution
+                .total_claimed
+                .checked_add(amount)
+                .ok_or(DividendError::Overflow)?;
+            distribution.range_claimed = distribution
+                .range_claimed
+                .checked_add(amount)
+                .ok_or(DividendError::Overflow)?;
+            distribution.exit(&crate::ID)?;
+            let withheld = bps_of(amount, bps)?;
+            total = total.checked_add(amount).ok_or(DividendError::Overflow)?;
+            total_withheld += withheld;
+            
+            emit!(DividendClaimed {
+                pool: pool_key,
+                user: holder,
+                recipient,
+                epoch,
+                amount,
+                withheld,
+                net: amount - withheld,
+            });
+        }
+        
+        require!(next_epoch > from_epoch, DividendError::ClaimRangeBlocked);
+        
+        let net = total - total_withheld;
+        if net > 0 {
+            let token_payout = TokenPayout::for_pool(
+                pool,
+                ctx.accounts.token_vault.as_ref(),
+                ctx.accounts.recipient_token_account.as_ref(),
+                ctx.accounts.token_program.as_ref(),
+            )?;
+            pay_from_vault(
+                pool_key,
+                &ctx.accounts.dividend_vault,
+                ctx.bumps.dividend_vault,
+                &recipient_account,
+                token_payout,
+                &ctx.accounts.system_program,
+                net,
+            )?;
+        }
+        let withheld = total_withheld;
+        if withheld > 0 {
+            let tax_payout = TokenPayout::for_pool(
+                pool,
+                ctx.accounts.token_vault.as_ref(),
+                ctx.accounts.tax_token_vault.as_ref(),
+                ctx.accounts.token_program.as_ref(),
+            )?;
+            pay_from_vault(
+                pool_key,
+                &ctx.accounts.dividend_vault,
+                ctx.bumps.dividend_vault,
+                &ctx.accounts.tax_vault,
+                tax_payout,
+                &ctx.accounts.system_program,
+                withheld,
+            )?;
         }
+        
+        cursor.pool = pool_key;
+        cursor.holder = holder;
+        cursor.next_epoch = next_epoch;
+        cursor.total_claimed = cursor
+            .total_claimed
+            .checked_add(total)
+            .ok_or(DividendError::Overflow)?;
+        cursor.bump = ctx.bumps.holder_cursor;
+        
+        emit!(DividendRangeClaimed {
+            pool: pool_key,
+            holder,
+            from_epoch,
+            to_epoch: next_epoch - 1,
+            amount: total,
+        });
+        
+        Ok(())
+    }
 
-        let raw = user_token_balance
-            .checked_mul(distribution.amount_per_token)
+    /// Holder: move the range-claim cursor past epochs a range claim stops at, such
+    /// as expired epochs or epochs in which the holder held nothing. Pass a
+    /// `[distribution_record, claim_record]` pair per epoch from the cursor on.
+    /// Unclaimed balance-snapshot epochs are forfeited: each gets a zero claim
+    /// record, paid for by the holder, which the views report as forfeited.
+    pub fn skip_epochs<'info>(ctx: Context<'_, '_, 'info, 'info, SkipEpochs<'info>>) -> Result<()> {
+        let holder = ctx.accounts.holder.key();
+        let pool_key = ctx.accounts.dividend_pool.key();
+        let pairs = ctx.remaining_accounts.chunks_exact(2);
+        require!(
+            pairs.remainder().is_empty() && pairs.len() > 0,
+            DividendError::InvalidClaimRange
+        );
+        
+        let cursor = &mut ctx.accounts.holder_cursor;
+        let from_epoch = cursor.next_epoch;
+        let end_epoch = from_epoch
+            .checked_add(pairs.len() as u64)
             .ok_or(DividendError::Overflow)?;
-        let remaining = distribution
-            .total_amount
-            .saturating_sub(distribution.total_claimed);
-        Ok(raw.min(remaining))
+        require!(
+            end_epoch <= ctx.accounts.dividend_pool.current_epoch,
+            DividendError::InvalidClaimRange
+        );
+        
+        let now = Clock::get()?.unix_timestamp;
+        let mut forfeited: u32 = 0;
+        for (epoch, pair) in (from_epoch..end_epoch).zip(pairs) {
+            let mut distribution = load_epoch_pair(pair, pool_key, epoch, &holder)?;
+            if !pair[1].data_is_empty()
+                || distribution.voided
+                || distribution.mode != DistributionMode::BalanceSnapshot
+            {
+                continue;
+            }
+            
+            let distribution_key = pair[0].key();
+            let (_, bump) = Pubkey::find_program_address(
+                &[b"claim", distribution_key.as_ref(), holder.as_ref()],
+                &crate::ID,
+            );
+            create_claim_record(
+                &ctx.accounts.holder,
+                &pair[1],
+                &[b"claim", distribution_key.as_ref(), holder.as_ref(), &[bump]],
+                &ctx.accounts.system_program,
+                &ClaimRecord {
+                    user: holder,
+                    distribution: distribution_key,
+                    epoch,
+                    claimed_at: now,
+                    claimed: true,
+                    bump,
+                    ..Default::default()
+                },
+            )?;
+            distribution.claim_count = distribution
+                .claim_count
+                .checked_add(1)
+                .ok_or(DividendError::Overflow)?;
+            distribution.exit(&crate::ID)?;
+            forfeited += 1;
+        }
+        
+        cursor.next_epoch = end_epoch;
+        
+        emit!(EpochsSkipped {
+            pool: pool_key,
+            holder,
+            from_epoch,
+            to_epoch: end_epoch - 1,
+            forfeited,
+        });
+        
+        Ok(())
     }
 
-    /// Pool authority: set `distribution.total_claimed` (e.g. after program upgrade to match
-    /// the sum of existing `ClaimRecord.amount_claimed` for this epoch).
-    pub fn set_distribution_total_claimed(
-        ctx: Context<SetDistributionTotalClaimed>,
+    /// Permissionless crank: pay a batch of holders their dividend for `epoch`, so a
+    /// keeper can settle a whole distribution. Pass one `EpochClaim` per holder and,
+    /// in `remaining_accounts`, `[holder, claim_record, holder_cursor, tax_profile,
+    /// payout_preference, recipient]` per holder. `payout_preference` is the holder's
+    /// preference address, which may not exist. `recipient` is the wallet paid, the
+    /// preference's payout address or else the holder, or for SPL pools a token
+    /// account it owns.
+    ///
+    /// Each payment creates the holder's `ClaimRecord`, paid for by the keeper,
+    /// unless it settles on the holder's range-claim cursor, which must then be
+    /// passed writable.
+    /// Holders already paid by a claim, a range claim, or an earlier push are
+    /// skipped, as are wallets owned by a program, which claim through a redirect.
+    /// An entry whose proof or recipient does not check out is skipped with a
+    /// `PushEntrySkipped` event rather than failing the batch.
+    pub fn push_dividends<'info>(
+        ctx: Context<'_, '_, 'info, 'info, PushDividends<'info>>,
         epoch: u64,
-        new_total_claimed: u64,
+        claims: Vec<EpochClaim>,
     ) -> Result<()> {
-        let dist = &mut ctx.accounts.distribution_record;
+        let pool = &ctx.accounts.dividend_pool;
+        let pool_key = pool.key();
         require!(
-            new_total_claimed <= dist.total_amount,
-            DividendError::InvalidAmount
+            !claims.is_empty() && claims.len() * 6 == ctx.remaining_accounts.len(),
+            DividendError::InvalidPushBatch
         );
-        dist.total_claimed = new_total_claimed;
-        emit!(DistributionClaimedTotalSynced {
-            pool: ctx.accounts.dividend_pool.key(),
+        
+        let distribution = &mut ctx.accounts.distribution_record;
+        let distribution_key = distribution.key();
+        let now = Clock::get()?.unix_timestamp;
+        require!(!distribution.voided, DividendError::DistributionVoided);
+        require!(
+            distribution.mode == DistributionMode::BalanceSnapshot,
+            DividendError::WrongDistributionMode
+        );
+        require!(!distribution.is_expired(now), DividendError::ClaimExpired);
+        require!(
+            distribution.snapshot_root != [0u8; 32],
+            DividendError::SnapshotPending
+        );
+        let mut paid: u32 = 0;
+        let mut skipped: u32 = 0;
+        let mut total: u64 = 0;
+        let mut total_withheld: u64 = 0;
+        let batches = claims.iter().zip(ctx.remaining_accounts.chunks_exact(6));
+        for (claim, accounts) in batches {
+            let holder = accounts[0].key();
+            let (claim_record, claim_bump) = Pubkey::find_program_address(
+                &[b"claim", distribution_key.as_ref(), holder.as_ref()],
+                &crate::ID,
+            );
+            let (holder_cursor, _) = Pubkey::find_program_address(
+                &[b"cursor", pool_key.as_ref(), holder.as_ref()],
+                &crate::ID,
+            );
+            let (tax_profile, _) = Pubkey::find_program_address(
+                &[b"tax_profile", pool_key.as_ref(), holder.as_ref()],
+                &crate::ID,
+            );
+            let (payout_preference, _) = Pubkey::find_program_address(
+                &[b"payout", pool_key.as_ref(), holder.as_ref()],
+                &crate::ID,
+            );
+            require_keys_eq!(accounts[1].key(), claim_record, DividendError::InvalidPushBatch);
+            require_keys_eq!(accounts[2].key(), holder_cursor, DividendError::InvalidPushBatch);
+            require_keys_eq!(accounts[3].key(), tax_profile, DividendError::InvalidPushBatch);
+            require_keys_eq!(accounts[4].key(), payout_preference, DividendError::InvalidPushBatch);
+            
+            if !accounts[1].data_is_empty()
+                || cursor_settles(&accounts[2], epoch)?
+                || accounts[0].owner != &System::id()
+            {
+                skipped += 1;
+                continue;
+            }
+            
+            let entry = snapshot_claim_amount(distribution, &holder, claim.snapshot_balance, &claim.proof)
+                .and_then(|amount| Ok((amount, push_recipient(pool, holder, accounts)?)));
+            let (amount, (recipient, recipient_token_account)) = match entry {
+                Ok(entry) => entry,
+                Err(error) => {
+                    emit!(PushEntrySkipped {
+                        pool: pool_key,
+                        epoch,
+                        holder,
+                        error_code: match error {
+                            Error::AnchorError(error) => error.error_code_number,
+                            Error::ProgramError(_) => 0,
+                        },
+                    });
+                    skipped += 1;
+                    continue;
+                }
+            };
+            let withheld = bps_of(amount, withholding_bps(&accounts[3])?)?;
+            let net = amount - withheld;
+            let token_payout = TokenPayout::for_pool(
+                pool,
+                ctx.accounts.token_vault.as_ref(),
+                recipient_token_account.as_ref(),
+                ctx.accounts.token_program.as_ref(),
+            )?;
+            pay_from_vault(
+                pool_key,
+                &ctx.accounts.dividend_vault,
+                ctx.bumps.dividend_vault,
+                &accounts[5],
+                token_payout,
+                &ctx.accounts.system_program,
+                net,
+            )?;
+            
+            distribution.total_claimed = distribution
+                .total_claimed
+                .checked_add(amount)
+                .ok_or(DividendError::Overflow)?;
+            if !settle_on_cursor(&accounts[2], distribution, epoch, claim.snapshot_balance, amount)? {
+                distribution.claim_count = distribution
+                    .claim_count
+                    .checked_add(1)
+                    .ok_or(DividendError::Overflow)?;
+                create_claim_record(
+                    &ctx.accounts.keeper,
+                    &accounts[1],
+                    &[b"claim", distribution_key.as_ref(), holder.as_ref(), &[claim_bump]],
+                    &ctx.accounts.system_program,
+                    &ClaimRecord {
+                        user: holder,
+                        distribution: distribution_key,
+                        epoch,
+                        amount_claimed: amount,
+                        claimed_at: now,
+                        claimed: true,
+                        bump: claim_bump,
+                        reinvested_amount: 0,
+                        tokens_reinvested: 0,
+                        withheld_amount: withheld,
+                        net_amount: net,
+                    },
+                )?;
+            }
+            
+            paid += 1;
+            total = total.checked_add(amount).ok_or(DividendError::Overflow)?;
+            total_withheld += withheld;
+            
+            emit!(DividendClaimed {
+                pool: pool_key,
+                user: holder,
+                recipient,
+                epoch,
+                amount,
+                withheld,
+                net,
+            });
+        }
+        
+        let withheld = total_withheld;
+        if withheld > 0 {
+            let tax_payout = TokenPayout::for_pool(
+                pool,
+                ctx.accounts.token_vault.as_ref(),
+                ctx.accounts.tax_token_vault.as_ref(),
+                ctx.accounts.token_program.as_ref(),
+            )?;
+            pay_from_vault(
+                pool_key,
+                &ctx.accounts.dividend_vault,
+                ctx.bumps.dividend_vault,
+                &ctx.accounts.tax_vault,
+                tax_payout,
+                &ctx.accounts.system_program,
+                withheld,
+            )?;
+        }
+        
+        emit!(DividendsPushed {
+            pool: pool_key,
             epoch,
-            total_claimed: new_total_claimed,
+            keeper: ctx.accounts.keeper.key(),
+            paid,
+            skipped,
+            amount: total,
         });
+        
         Ok(())
     }
 
-    /// Update pool authority (transfer ownership)
-    pub fn update_authority(ctx: Context<UpdateAuthority>, new_authority: Pubkey) -> Result<()> {
+    /// Claimable, already-claimed and forfeited dividends of `holder` for one epoch,
+    /// given their snapshot balance and proof (view function, call through
+    /// simulation). Nothing is claimable from expired epochs, epochs awaiting their
+    /// snapshot root, or merkle-amount epochs.
+    pub fn get_claimable_amount(
+        ctx: Context<GetClaimableAmount>,
+        epoch: u64,
+        snapshot_balance: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<ClaimableAmount> {
+        let settled = cursor_settles(&ctx.accounts.holder_cursor, epoch)?;
+        claimable_in(
+            &ctx.accounts.distribution_record,
+            &ctx.accounts.holder.key(),
+            snapshot_balance,
+            &proof,
+            claim_record_amount(&ctx.accounts.claim_record)?,
+            settled,
+            Clock::get()?.unix_timestamp,
+        )
+    }
+
+    /// Totals of `get_claimable_amount` over the epochs from `from_epoch` onwards,
+    /// with arguments and `remaining_accounts` laid out as for `claim_dividends_range`.
+    /// `claim_deadline` is the earliest deadline among epochs with a claimable amount.
+    pub fn get_total_claimable<'info>(
+        ctx: Context<'_, '_, 'info, 'info, GetTotalClaimable<'info>>,
+        from_epoch: u64,
+        claims: Vec<EpochClaim>,
+    ) -> Result<ClaimableAmount> {
+        require!(
+            claims.len() * 2 == ctx.remaining_accounts.len(),
+            DividendError::InvalidClaimRange
+        );
+        
+        let holder = ctx.accounts.holder.key();
+        let pool_key = ctx.accounts.dividend_pool.key();
+        let cursor = load_cursor(&ctx.accounts.holder_cursor)?;
+        let now = Clock::get()?.unix_timestamp;
+        
+        let mut total = ClaimableAmount::default();
+        let epochs = (from_epoch..).zip(&claims);
+        for ((epoch, claim), pair) in epochs.zip(ctx.remaining_accounts.chunks_exact(2)) {
+            let distribution = load_epoch_pair(pair, pool_key, epoch, &holder)?;
+            let amount = claimable_in(
+                &distribution,
+                &holder,
+                claim.snapshot_balance,
+                &claim.proof,
+                claim_record_amount(&pair[1])?,
+                cursor.as_ref().is_some_and(|cursor| cursor.settles(epoch)),
+                now,
+            )?;
+            
+            total.claimable_now = total
+                .claimable_now
+                .checked_add(amount.claimable_now)
+                .ok_or(DividendError::Overflow)?;
+            total.already_claimed = total
+                .already_claimed
+                .checked_add(amount.already_claimed)
+                .ok_or(DividendError::Overflow)?;
+            total.forfeited = total
+                .forfeited
+                .checked_add(amount.forfeited)
+                .ok_or(DividendError::Overflow)?;
+            if amount.claimable_now > 0
+                && amount.claim_deadline != 0
+                && (total.claim_deadline == 0 || amount.claim_deadline < total.claim_deadline)
+            {
+                total.claim_deadline = amount.claim_deadline;
+            }
+        }
+        
+        Ok(total)
+    }
+
+    /// Start a distribution whose per-holder amounts were computed off-chain.
+    /// `merkle_root` commits to `dividend_merkle::amount_leaf(index, holder, amount)`
+    /// for `holder_count` holders whose amounts sum to `total_amount`; any undistributed
+    /// remainder of the epoch's deposits carries over to the next epoch. The
+    /// distribution interval applies as for `start_distribution`.
+    ///
+    /// The management fee and reserve are taken first, as for `start_distribution`;
+    /// `total_amount` can be at most what is left.
+    pub fn start_merkle_distribution(
+        ctx: Context<StartMerkleDistribution>,
+        merkle_root: [u8; 32],
+        total_amount: u64,
+        holder_count: u32,
+        override_interval: bool,
+    ) -> Result<()> {
         let pool = &mut ctx.accounts.dividend_pool;
+        let clock = Clock::get()?;
         
-        emit!(AuthorityUpdated {
+        require!(!pool.streaming, DividendError::WrongPoolMode);
+        require!(!pool.has_waterfall, DividendError::WaterfallMismatch);
+        require!(
+            override_interval || pool.distribution_due(clock.unix_timestamp)?,
+            DividendError::DistributionNotDue
+        );
+        require!(total_amount > 0, DividendError::NoDividendsToDistribute);
+        require!(merkle_root != [0u8; 32], DividendError::InvalidSnapshot);
+        require!(
+            holder_count > 0 && holder_count <= MAX_MERKLE_HOLDERS,
+            DividendError::InvalidHolderCount
+        );
+        
+        let distribution = &mut ctx.accounts.distribution_record;
+        distribution.pool = pool.key();
+        distribution.epoch = pool.current_epoch;
+        distribution.total_amount = total_amount;
+        distribution.total_token_supply = 0;
+        distribution.amount_per_token = 0;
+        distribution.distributed_at = clock.unix_timestamp;
+        distribution.total_claimed = 0;
+        distribution.bump = ctx.bumps.distribution_record;
+        distribution.snapshot_slot = clock.slot;
+        distribution.snapshot_root = merkle_root;
+        distribution.mode = DistributionMode::MerkleAmounts;
+        distribution.holder_count = holder_count;
+        distribution.excluded_supply = 0;
+        distribution.claim_deadline = pool.claim_deadline(clock.unix_timestamp)?;
+        distribution.swept = false;
+        distribution.voided = false;
+        distribution.corrects_epoch = None;
+        distribution.claim_count = 0;
+        distribution.range_claimed = 0;
+        
+        let bitmap = &mut ctx.accounts.claim_bitmap;
+        bitmap.distribution = distribution.key();
+        bitmap.claimed = vec![0u8; ClaimBitmap::bytes_for(holder_count)];
+        bitmap.bump = ctx.bumps.claim_bitmap;
+        
+        pool.total_distributed = pool
+            .total_distributed
+            .checked_add(total_amount)
+            .ok_or(DividendError::Overflow)?;
+        pool.last_distribution_time = clock.unix_timestamp;
+        pool.current_epoch = pool.current_epoch.checked_add(1).ok_or(DividendError::Overflow)?;
+        let deductions = pool.fund_merkle_distribution(total_amount)?;
+        
+        emit!(MerkleDistributionStarted {
             pool: pool.key(),
-            old_authority: pool.authority,
-            new_authority,
+            epoch: distribution.epoch,
+            total_amount,
+            merkle_root,
+            holder_count,
+            management_fee: deductions.management_fee,
+            reserve: deductions.reserve,
         });
         
-        pool.authority = new_authority;
-        
-        Ok(())
+        pay_deductions(
+            &ctx.accounts.dividend_pool,
+            &deductions,
+            DeductionPayees {
+                dividend_vault: &ctx.accounts.dividend_vault,
+                vault_bump: ctx.bumps.dividend_vault,
+                manager: &ctx.accounts.manager,
+                reserve_vault: &ctx.accounts.reserve_vault,
+                token_vault: ctx.accounts.token_vault.as_ref(),
+                manager_token_account: ctx.accounts.manager_token_account.as_ref(),
+                reserve_token_vault: ctx.accounts.reserve_token_vault.as_ref(),
+                token_program: ctx.accounts.token_program.as_ref(),
+                system_program: &ctx.accounts.system_program,
+            },
+        )
     }
-}
-
-// ============================================================================
-// Account Structures
-// ============================================================================
 
-#[account]
-#[derive(Default)]
-pub struct DividendPool {
-    /// Authority who can deposit dividends and manage the pool
-    pub authority: Pubkey,
-    /// The property token mint
-    pub property_mint: Pubkey,
-    /// Vault holding SOL for dividends
-    pub dividend_vault: Pubkey,
-    /// Property identifier
-    pub property_id: String,
-    /// Total SOL distributed all time
-    pub total_distributed: u64,
-    /// Current distribution epoch
-    pub current_epoch: u64,
+    /// Claim a precomputed dividend from a merkle-amount distribution
+    pub fn claim_merkle_dividend(
+        ctx: Context<ClaimMerkleDividend>,
+        epoch: u64,
+        index: u32,
+        amount: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let distribution = &mut ctx.accounts.distribution_record;
+        
+        require!(!distribution.voided, DividendError::DistributionVoided);
+        require!(
+            distribution.mode == DistributionMode::MerkleAmounts,
+            DividendError::WrongDistributionMode
+        );
+        require!(
+            !distribution.is_expired(Clock::get()?.unix_timestamp),
+            DividendError::ClaimExpired
+        );
+        require!(index < distribution.holder_count, DividendError::InvalidHolderCount);
+        require!(amount > 0, DividendError::NoDividendsToClaim);
+        require!(
+            verify_proof(
+                &distribution.snapshot_root,
+                amount_leaf(index, &ctx.accounts.user.key(), amount),
+                &proof,
+            ),
+            DividendError::InvalidSnapshotProof
+        );
+        
+        let bitmap = &mut ctx.accounts.claim_bitmap;
+        require!(!bitmap.is_claimed(index), DividendError::AlreadyClaimed);
+        
+        let remaining = distribution
+            .total_amount
+            .checked_sub(distribution.total_claimed)
+            .ok_or(DividendError::Overflow)?;
+        require!(amount <= remaining, DividendError::InsufficientDeposits);
+        
+        let withheld = bps_of(amount, withholding_bps(&ctx.accounts.tax_profile)?)?;
+        let net = amount - withheld;
+        
+        let pool_key = ctx.accounts.dividend_pool.key();
+        let token_payout = TokenPayout::for_pool(
+            &ctx.accounts.dividend_pool,
+            ctx.accounts.token_vault.as_ref(),
+            ctx.accounts.recipient_token_account.as_ref(),
+            ctx.accounts.token_program.as_ref(),
+        )?;
+        pay_from_vault(
+            pool_key,
+            &ctx.accounts.dividend_vault,
+            ctx.bumps.dividend_vault,
+            &ctx.accounts.user,
+            token_payout,
+            &ctx.accounts.system_program,
+            net,
+        )?;
+        if withheld > 0 {
+            let tax_payout = TokenPayout::for_pool(
+                &ctx.accounts.dividend_pool,
+                ctx.accounts.token_vault.as_ref(),
+                ctx.accounts.tax_token_vault.as_ref(),
+                ctx.accounts.token_program.as_ref(),
+            )?;
+            pay_from_vault(
+                pool_key,
+                &ctx.accounts.dividend_vault,
+                ctx.bumps.dividend_vault,
+                &ctx.accounts.tax_vault,
+                tax_payout,
+                &ctx.accounts.system_program,
+                withheld,
+            )?;
+        }
+        
+        bitmap.set_claimed(index);
+        distribution.total_claimed = distribution
+            .total_claimed
+            .checked_add(amount)
+            .ok_or(DividendError::Overflow)?;
+        
+        emit!(DividendClaimed {
+            pool: pool_key,
+            user: ctx.accounts.user.key(),
+            recipient: ctx.accounts.user.key(),
+            epoch,
+            amount,
+            withheld,
+            net,
+        });
+        
+        Ok(())
+    }
+
+    /// Switch the pool to streaming mode: each deposit is paid out linearly over
+    /// `stream_period` seconds to holders with a streaming position, in proportion
+    /// to the balances checkpointed by `checkpoint_holder`. Epoch distributions are
+    /// disabled afterwards.
+    pub fn enable_streaming(ctx: Context<EnableStreaming>, stream_period: i64) -> Result<()> {
+        require!(stream_period > 0, DividendError::InvalidStreamPeriod);
+        
+        let pool = &mut ctx.accounts.dividend_pool;
+        require!(!pool.streaming, DividendError::WrongPoolMode);
+        require!(!pool.has_waterfall, DividendError::WaterfallMismatch);
+        require!(
+            pool.total_deposited_current_epoch == 0,
+            DividendError::WrongPoolMode
+        );
+        pool.streaming = true;
+        
+        let now = Clock::get()?.unix_timestamp;
+        let stream = &mut ctx.accounts.stream_state;
+        stream.pool = pool.key();
+        stream.property_mint = pool.property_mint;
+        stream.stream_period = stream_period;
+        stream.reward_rate = 0;
+        stream.period_finish = now;
+        stream.last_update = now;
+        stream.reward_per_token = 0;
+        stream.unallocated = 0;
+        stream.total_checkpointed = 0;
+        stream.bump = ctx.bumps.stream_state;
+        
+        emit!(StreamingEnabled {
+            pool: pool.key(),
+            stream_period,
+        });
+        
+        Ok(())
+    }
+
+    /// Checkpoint a holder's streaming position against the property tokens in
+    /// their associated token account. Only the holder can open their position;
+    /// after that anyone can checkpoint it. A position earns on the balance
+    /// recorded at its last checkpoint, and only for tokens still held at the next
+    /// one, so the sender of a transfer checkpoints before it and the receiver
+    /// after it.
+    pub fn checkpoint_holder(ctx: Context<CheckpointHolder>) -> Result<()> {
+        let owner = ctx.accounts.owner.key();
+        let position = &mut ctx.accounts.stream_position;
+        if position.owner == Pubkey::default() {
+            require_keys_eq!(ctx.accounts.payer.key(), owner, DividendError::Unauthorized);
+            position.pool = ctx.accounts.dividend_pool.key();
+            position.owner = owner;
+            position.bump = ctx.bumps.stream_position;
+        }
+        
+        let stream = &mut ctx.accounts.stream_state;
+        stream.accrue(Clock::get()?.unix_timestamp)?;
+        let balance = holder_balance(&ctx.accounts.holder_token_account, &owner, &stream.property_mint)?;
+        position.checkpoint(stream, balance)?;
+        
+        emit!(HolderCheckpointed {
+            pool: position.pool,
+            owner,
+            balance,
+            total_checkpointed: stream.total_checkpointed,
+        });
+        
+        Ok(())
+    }
+
+    /// Claim dividends streamed to a holder's position so far, checkpointing it
+    pub fn claim_streamed(ctx: Context<ClaimStreamed>) -> Result<()> {
+        let owner = ctx.accounts.owner.key();
+        let stream = &mut ctx.accounts.stream_state;
+        stream.accrue(Clock::get()?.unix_timestamp)?;
+        let balance = holder_balance(&ctx.accounts.holder_token_account, &owner, &stream.property_mint)?;
+        let position = &mut ctx.accounts.stream_position;
+        position.checkpoint(stream, balance)?;
+        
+        let amount = position.accrued;
+        require!(amount > 0, DividendError::NoDividendsToClaim);
+        let withheld = bps_of(amount, withholding_bps(&ctx.accounts.tax_profile)?)?;
+        let net = amount - withheld;
+        
+        let pool = &ctx.accounts.dividend_pool;
+        let token_payout = TokenPayout::for_pool(
+            pool,
+            ctx.accounts.token_vault.as_ref(),
+            ctx.accounts.recipient_token_account.as_ref(),
+            ctx.accounts.token_program.as_ref(),
+        )?;
+        pay_from_vault(
+            pool.key(),
+            &ctx.accounts.dividend_vault,
+            ctx.bumps.dividend_vault,
+            &ctx.accounts.owner,
+            token_payout,
+            &ctx.accounts.system_program,
+            net,
+        )?;
+        if withheld > 0 {
+            let tax_payout = TokenPayout::for_pool(
+                pool,
+                ctx.accounts.token_vault.as_ref(),
+                ctx.accounts.tax_token_vault.as_ref(),
+                ctx.accounts.token_program.as_ref(),
+            )?;
+            pay_from_vault(
+                pool.key(),
+                &ctx.accounts.dividend_vault,
+                ctx.bumps.dividend_vault,
+                &ctx.accounts.tax_vault,
+                tax_payout,
+                &ctx.accounts.system_program,
+                withheld,
+            )?;
+        }
+        
+        position.accrued = 0;
+        position.total_claimed = position
+            .total_claimed
+            .checked_add(amount)
+            .ok_or(DividendError::Overflow)?;
+        
+        emit!(StreamClaimed {
+            pool: pool.key(),
+            owner: position.owner,
+            amount,
+            withheld,
+            net,
+        });
+        
+        Ok(())
+    }
+
+    /// Void an erroneous distribution before anything is claimed from it. Its amount
+    /// returns to the pool's undistributed deposits. `reason_hash` commits to the
+    /// off-chain record of the correction.
+    pub fn void_distribution(
+        ctx: Context<CorrectDistribution>,
+        epoch: u64,
+        reason_hash: [u8; 32],
+    ) -> Result<()> {
+        require!(reason_hash != [0u8; 32], DividendError::MissingJustification);
+        
+        let distribution = &mut ctx.accounts.distribution_record;
+        require!(!distribution.voided, DividendError::DistributionVoided);
+        require!(!distribution.swept, DividendError::AlreadySwept);
+        require!(
+            distribution.total_claimed == 0 && distribution.claim_count == 0,
+            DividendError::ClaimsStarted
+        );
+        distribution.voided = true;
+        
+        let pool = &mut ctx.accounts.dividend_pool;
+        return_to_waterfall(pool, ctx.accounts.waterfall.as_mut(), distribution.total_amount)?;
+        pool.total_deposited_current_epoch = pool
+            .total_deposited_current_epoch
+            .checked_add(distribution.total_amount)
+            .ok_or(DividendError::Overflow)?;
+        pool.total_distributed = pool
+            .total_distributed
+            .checked_sub(distribution.total_amount)
+            .ok_or(DividendError::Overflow)?;
+        
+        emit!(DistributionVoided {
+            pool: pool.key(),
+            epoch,
+            amount_returned: distribution.total_amount,
+            reason_hash,
+        });
+        
+        Ok(())
+    }
+
+    /// Open a corrective top-up epoch paying `amount` more for `corrects_epoch`, from
+    /// undistributed deposits. It reuses the corrected epoch's balance snapshot, so
+    /// holders claim it with the same proofs. It does not count against the
+    /// distribution interval and is not charged the management fee or reserve.
+    pub fn start_corrective_distribution(
+        ctx: Context<StartCorrectiveDistribution>,
+        corrects_epoch: u64,
+        amount: u64,
+        reason_hash: [u8; 32],
+    ) -> Result<()> {
+        require!(amount > 0, DividendError::InvalidAmount);
+        require!(reason_hash != [0u8; 32], DividendError::MissingJustification);
+        
+        let corrected = &ctx.accounts.corrected_distribution;
+        require!(!corrected.voided, DividendError::DistributionVoided);
+        require!(
+            corrected.mode == DistributionMode::BalanceSnapshot,
+            DividendError::WrongDistributionMode
+        );
+        require!(
+            corrected.snapshot_root != [0u8; 32],
+            DividendError::SnapshotPending
+        );
+        
+        let pool = &mut ctx.accounts.dividend_pool;
+        require!(!pool.streaming, DividendError::WrongPoolMode);
+        require!(
+            amount <= pool.total_deposited_current_epoch,
+            DividendError::InsufficientDeposits
+        );
+        
+        let total_supply = corrected.total_token_supply;
+        let amount_per_token = (amount as u128)
+            .checked_mul(AMOUNT_PER_TOKEN_SCALE)
+            .ok_or(DividendError::Overflow)?
+            .checked_div(total_supply as u128)
+            .ok_or(DividendError::Overflow)?;
+        let distributable = entitlement(total_supply, amount_per_token)?;
+        require!(distributable > 0, DividendError::NoDividendsToDistribute);
+        
+        let now = Clock::get()?.unix_timestamp;
+        let distribution = &mut ctx.accounts.distribution_record;
+        distribution.pool = pool.key();
+        distribution.epoch = pool.current_epoch;
+        distribution.total_amount = distributable;
+        distribution.total_token_supply = total_supply;
+        distribution.amount_per_token = amount_per_token;
+        distribution.distributed_at = now;
+        distribution.total_claimed = 0;
+        distribution.bump = ctx.bumps.distribution_record;
+        distribution.snapshot_slot = corrected.snapshot_slot;
+        distribution.snapshot_root = corrected.snapshot_root;
+        distribution.mode = DistributionMode::BalanceSnapshot;
+        distribution.holder_count = 0;
+        distribution.excluded_supply = corrected.excluded_supply;
+        distribution.claim_deadline = pool.claim_deadline(now)?;
+        distribution.swept = false;
+        distribution.voided = false;
+        distribution.corrects_epoch = Some(corrects_epoch);
+        distribution.claim_count = 0;
+        distribution.range_claimed = 0;
+        
+        pool.total_deposited_current_epoch -= distributable;
+        pool.deductible_deposits = pool.deductible_deposits.saturating_sub(distributable);
+        pool.total_distributed = pool
+            .total_distributed
+            .checked_add(distributable)
+            .ok_or(DividendError::Overflow)?;
+        pool.current_epoch = pool.current_epoch.checked_add(1).ok_or(DividendError::Overflow)?;
+        
+        emit!(CorrectiveDistributionStarted {
+            pool: pool.key(),
+            epoch: distribution.epoch,
+            corrects_epoch,
+            total_amount: distributable,
+            amount_per_token,
+            reason_hash,
+        });
+        
+        Ok(())
+    }
+
+    /// Recompute a balance-snapshot distribution's `total_claimed` from its claim
+    /// records plus the amount settled by range claims. The records are passed in
+    /// `remaining_accounts` over as many calls as needed, in ascending address
+    /// order across calls. The running sum is kept in a `ClaimRecompute` account
+    /// and committed, closing that account, once every record has been counted.
+    /// `from_start` discards a recompute in progress; one is also rejected if a
+    /// claim was made since it started.
+    ///
+    /// The recomputed total can only raise `total_claimed`. A migrated
+    /// distribution's `claim_count` comes from the authority, so a count that
+    /// leaves records out would otherwise lower it and let `sweep_unclaimed` take
+    /// funds that were already paid.
+    pub fn recompute_total_claimed<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RecomputeTotalClaimed<'info>>,
+        epoch: u64,
+        reason_hash: [u8; 32],
+        from_start: bool,
+    ) -> Result<()> {
+        require!(reason_hash != [0u8; 32], DividendError::MissingJustification);
+        
+        let distribution = &mut ctx.accounts.distribution_record;
+        require!(
+            distribution.mode == DistributionMode::BalanceSnapshot,
+            DividendError::WrongDistributionMode
+        );
+        
+        let distribution_key = distribution.key();
+        let recompute = &mut ctx.accounts.recompute;
+        if from_start || recompute.distribution != distribution_key {
+            recompute.distribution = distribution_key;
+            recompute.claim_count = distribution.claim_count;
+            recompute.range_claimed = distribution.range_claimed;
+            recompute.records_counted = 0;
+            recompute.amount_claimed = 0;
+            recompute.last_record = Pubkey::default();
+            recompute.bump = ctx.bumps.recompute;
+        } else {
+            require!(
+                recompute.claim_count == distribution.claim_count
+                    && recompute.range_claimed == distribution.range_claimed,
+                DividendError::RecomputeOutdated
+            );
+        }
+        
+        for info in ctx.remaining_accounts {
+            // Strictly ascending addresses, across batches too, rule out
+            // counting one record twice
+            require!(info.key() > recompute.last_record, DividendError::IncompleteClaimRecords);
+            recompute.last_record = info.key();
+            
+            let record = Account::<ClaimRecord>::try_from(info)?;
+            require_keys_eq!(
+                record.distribution,
+                distribution_key,
+                DividendError::IncompleteClaimRecords
+            );
+            recompute.amount_claimed = recompute.amount_claimed
+                .checked_add(record.amount_claimed)
+                .ok_or(DividendError::Overflow)?;
+            recompute.records_counted = recompute.records_counted
+                .checked_add(1)
+                .ok_or(DividendError::Overflow)?;
+        }
+        require!(
+            recompute.records_counted <= recompute.claim_count,
+            DividendError::IncompleteClaimRecords
+        );
+        
+        if recompute.records_counted < recompute.claim_count {
+            emit!(TotalClaimedRecomputeProgress {
+                pool: ctx.accounts.dividend_pool.key(),
+                epoch,
+                records_counted: recompute.records_counted,
+                claim_records: recompute.claim_count,
+            });
+            return Ok(());
+        }
+        
+        let claimed = recompute.range_claimed
+            .checked_add(recompute.amount_claimed)
+            .ok_or(DividendError::Overflow)?;
+        require!(claimed <= distribution.total_amount, DividendError::InvalidAmount);
+        require!(
+            claimed >= distribution.total_claimed,
+            DividendError::RecomputeBelowTotalClaimed
+        );
+        
+        let previous_total_claimed = distribution.total_claimed;
+        distribution.total_claimed = claimed;
+        
+        emit!(TotalClaimedRecomputed {
+            pool: ctx.accounts.dividend_pool.key(),
+            epoch,
+            previous_total_claimed,
+            total_claimed: claimed,
+            claim_records: distribution.claim_count,
+            reason_hash,
+        });
+        
+        ctx.accounts.recompute.close(ctx.accounts.authority.to_account_info())?;
+        
+        Ok(())
+    }
+
+    /// Set how long future distributions stay claimable (`0` never expires) and
+    /// what happens to their unclaimed remainder afterwards
+    pub fn set_unclaimed_policy(
+        ctx: Context<SetUnclaimedPolicy>,
+        claim_period_days: u64,
+        policy: UnclaimedPolicy,
+        treasury: Pubkey,
+    ) -> Result<()> {
+        require!(
+            policy != UnclaimedPolicy::Treasury || treasury != Pubkey::default(),
+            DividendError::InvalidTreasury
+        );
+        
+        let pool = &mut ctx.accounts.dividend_pool;
+        pool.claim_period_days = claim_period_days;
+        pool.unclaimed_policy = policy.clone();
+        pool.treasury = treasury;
+        
+        emit!(UnclaimedPolicyUpdated {
+            pool: pool.key(),
+            claim_period_days,
+            policy,
+            treasury,
+        });
+        
+        Ok(())
+    }
+
+    /// Settle a distribution whose claim deadline has passed: its unclaimed
+    /// remainder rolls into the pool's undistributed deposits, or is paid to the
+    /// pool treasury, according to the pool's policy. Callable by anyone.
+    pub fn sweep_unclaimed(ctx: Context<SweepUnclaimed>, epoch: u64) -> Result<()> {
+        let distribution = &mut ctx.accounts.distribution_record;
+        require!(
+            distribution.is_expired(Clock::get()?.unix_timestamp),
+            DividendError::ClaimPeriodOpen
+        );
+        require!(!distribution.swept, DividendError::AlreadySwept);
+        require!(!distribution.voided, DividendError::DistributionVoided);
+        
+        let unclaimed = distribution
+            .total_amount
+            .checked_sub(distribution.total_claimed)
+            .ok_or(DividendError::Overflow)?;
+        distribution.swept = true;
+        
+        let pool = &mut ctx.accounts.dividend_pool;
+        let treasury = match pool.unclaimed_policy {
+            UnclaimedPolicy::Rollover => {
+                return_to_waterfall(pool, ctx.accounts.waterfall.as_mut(), unclaimed)?;
+                pool.total_deposited_current_epoch = pool
+                    .total_deposited_current_epoch
+                    .checked_add(unclaimed)
+                    .ok_or(DividendError::Overflow)?;
+                None
+            }
+            UnclaimedPolicy::Treasury => {
+                let treasury = ctx
+                    .accounts
+                    .treasury
+                    .as_ref()
+                    .ok_or(DividendError::InvalidTreasury)?;
+                if unclaimed > 0 {
+                    let token_payout = TokenPayout::for_pool(
+                        pool,
+                        ctx.accounts.token_vault.as_ref(),
+                        ctx.accounts.treasury_token_account.as_ref(),
+                        ctx.accounts.token_program.as_ref(),
+                    )?;
+                    pay_from_vault(
+                        pool.key(),
+                        &ctx.accounts.dividend_vault,
+                        ctx.bumps.dividend_vault,
+                        treasury,
+                        token_payout,
+                        &ctx.accounts.system_program,
+                        unclaimed,
+                    )?;
+                }
+                Some(pool.treasury)
+            }
+        };
+        
+        emit!(UnclaimedSwept {
+            pool: pool.key(),
+            epoch,
+            amount: unclaimed,
+            treasury,
+        });
+        
+        Ok(())
+    }
+
+    /// Holder: send future claims to `payout_address` and optionally let `delegate`
+    /// trigger claims on the holder's behalf. Delegated claims always pay
+    /// `payout_address`.
+    pub fn set_payout_preference(
+        ctx: Context<SetPayoutPreference>,
+        payout_address: Pubkey,
+        delegate: Option<Pubkey>,
+    ) -> Result<()> {
+        let preference = &mut ctx.accounts.payout_preference;
+        preference.pool = ctx.accounts.dividend_pool.key();
+        preference.holder = ctx.accounts.holder.key();
+        preference.payout_address = payout_address;
+        preference.delegate = delegate;
+        preference.updated_at = Clock::get()?.unix_timestamp;
+        preference.bump = ctx.bumps.payout_preference;
+        
+        emit!(PayoutPreferenceUpdated {
+            pool: preference.pool,
+            holder: preference.holder,
+            payout_address: Some(payout_address),
+            delegate,
+        });
+        
+        Ok(())
+    }
+
+    /// Holder: remove the payout preference, so claims pay the holder again
+    pub fn clear_payout_preference(ctx: Context<ClearPayoutPreference>) -> Result<()> {
+        emit!(PayoutPreferenceUpdated {
+            pool: ctx.accounts.dividend_pool.key(),
+            holder: ctx.accounts.holder.key(),
+            payout_address: None,
+            delegate: None,
+        });
+        
+        Ok(())
+    }
+
+    /// Hand the compliance role, which manages tax profiles and remits withheld tax,
+    /// to `compliance_authority`
+    pub fn set_compliance_authority(
+        ctx: Context<SetComplianceAuthority>,
+        compliance_authority: Pubkey,
+    ) -> Result<()> {
+        let pool = &mut ctx.accounts.dividend_pool;
+        
+        emit!(ComplianceAuthorityUpdated {
+            pool: pool.key(),
+            old_compliance_authority: pool.compliance_authority,
+            new_compliance_authority: compliance_authority,
+        });
+        
+        pool.compliance_authority = compliance_authority;
+        
+        Ok(())
+    }
+
+    /// Compliance: set the share of `holder`'s payouts withheld for tax
+    pub fn set_tax_profile(
+        ctx: Context<SetTaxProfile>,
+        holder: Pubkey,
+        withholding_bps: u16,
+        jurisdiction: [u8; 2],
+    ) -> Result<()> {
+        require!(withholding_bps <= 10_000, DividendError::InvalidWithholdingRate);
+        
+        let profile = &mut ctx.accounts.tax_profile;
+        profile.pool = ctx.accounts.dividend_pool.key();
+        profile.holder = holder;
+        profile.withholding_bps = withholding_bps;
+        profile.jurisdiction = jurisdiction;
+        profile.updated_at = Clock::get()?.unix_timestamp;
+        profile.bump = ctx.bumps.tax_profile;
+        
+        emit!(TaxProfileUpdated {
+            pool: profile.pool,
+            holder,
+            withholding_bps,
+            jurisdiction,
+        });
+        
+        Ok(())
+    }
+
+    /// Create the token account that collects withheld tax for an SPL pool
+    pub fn initialize_tax_token_vault(ctx: Context<InitializeTaxTokenVault>) -> Result<()> {
+        require!(
+            ctx.accounts.dividend_pool.payout_mint.is_some(),
+            DividendError::WrongPoolMode
+        );
+        Ok(())
+    }
+
+    /// Compliance: pay withheld tax out of the tax vault for remittance. SOL goes to
+    /// `destination`; SPL pools pay into `destination_token_account`. The SOL
+    /// vault keeps its rent-exempt minimum.
+    pub fn remit_withheld_tax(ctx: Context<RemitWithheldTax>, amount: u64) -> Result<()> {
+        require!(amount > 0, DividendError::InvalidAmount);
+        if ctx.accounts.dividend_pool.payout_mint.is_none() {
+            require!(
+                amount <= lamports_above_rent(&ctx.accounts.tax_vault)?,
+                DividendError::InsufficientTaxBalance
+            );
+        }
+        
+        let pool_key = ctx.accounts.dividend_pool.key();
+        let seeds = &[b"tax_vault", pool_key.as_ref(), &[ctx.bumps.tax_vault]];
+        let signer_seeds = &[&seeds[..]];
+        
+        match (
+            ctx.accounts.dividend_pool.payout_mint,
+            &ctx.accounts.tax_token_vault,
+            &ctx.accounts.destination_token_account,
+            &ctx.accounts.token_program,
+        ) {
+            (None, None, None, _) => anchor_lang::system_program::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.tax_vault.to_account_info(),
+                        to: ctx.accounts.destination.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                amount,
+            )?,
+            (Some(_), Some(tax_token_vault), Some(destination), Some(token_program)) => token::transfer(
+                CpiContext::new_with_signer(
+                    token_program.to_account_info(),
+                    Transfer {
+                        from: tax_token_vault.to_account_info(),
+                        to: destination.to_account_info(),
+                        authority: ctx.accounts.tax_vault.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                amount,
+            )?,
+            _ => return err!(DividendError::InvalidPayoutAccounts),
+        }
+        
+        emit!(WithheldTaxRemitted {
+            pool: pool_key,
+            destination: ctx.accounts.destination.key(),
+            amount,
+        });
+        
+        Ok(())
+    }
+
+    /// Allow `depositor` to deposit dividends into the pool, or re-enable them
+    pub fn add_depositor(ctx: Context<AddDepositor>, depositor: Pubkey) -> Result<()> {
+        let record = &mut ctx.accounts.depositor_record;
+        if record.pool == Pubkey::default() {
+            record.pool = ctx.accounts.dividend_pool.key();
+            record.depositor = depositor;
+            record.bump = ctx.bumps.depositor_record;
+        }
+        record.active = true;
+        record.added_at = Clock::get()?.unix_timestamp;
+        
+        emit!(DepositorUpdated {
+            pool: record.pool,
+            depositor,
+            active: true,
+        });
+        
+        Ok(())
+    }
+
+    /// Take a depositor off the allow-list. Their record is kept, with its totals.
+    pub fn remove_depositor(ctx: Context<RemoveDepositor>) -> Result<()> {
+        let record = &mut ctx.accounts.depositor_record;
+        record.active = false;
+        
+        emit!(DepositorUpdated {
+            pool: record.pool,
+            depositor: record.depositor,
+            active: false,
+        });
+        
+        Ok(())
+    }
+
+    /// Put the pool's distributions through a waterfall with a preferred share
+    /// class, whose holders are paid through `preferred_pool`. Each distribution
+    /// first pays the preferred return accrued at `preferred_return_bps` a year on
+    /// `preferred_capital`. Common holders then catch up until they have received
+    /// `catch_up_bps` of everything allocated, and the rest is split with
+    /// `residual_preferred_bps` going to the preferred class. Reconfiguring keeps
+    /// the accrued return and the allocation totals.
+    pub fn configure_waterfall(
+        ctx: Context<ConfigureWaterfall>,
+        preferred_capital: u64,
+        preferred_return_bps: u16,
+        catch_up_bps: u16,
+        residual_preferred_bps: u16,
+    ) -> Result<()> {
+        require!(
+            preferred_return_bps <= 10_000 && catch_up_bps < 10_000 && residual_preferred_bps <= 10_000,
+            DividendError::InvalidWaterfall
+        );
+        
+        let pool = &mut ctx.accounts.dividend_pool;
+        let preferred_pool = &ctx.accounts.preferred_pool;
+        require!(
+            preferred_pool.key() != pool.key()
+                && preferred_pool.payout_mint == pool.payout_mint
+                && !preferred_pool.streaming
+                && !preferred_pool.has_waterfall
+                && !pool.streaming,
+            DividendError::InvalidWaterfall
+        );
+        
+        let now = Clock::get()?.unix_timestamp;
+        let waterfall = &mut ctx.accounts.waterfall;
+        if pool.has_waterfall {
+            // Accrue at the old terms up to now before they change
+            waterfall.accrue(now)?;
+            require_keys_eq!(
+                waterfall.preferred_pool,
+                preferred_pool.key(),
+                DividendError::InvalidWaterfall
+            );
+        } else {
+            waterfall.pool = pool.key();
+            waterfall.preferred_pool = preferred_pool.key();
+            waterfall.preferred_mint = preferred_pool.property_mint;
+            waterfall.last_accrual = now;
+            waterfall.bump = ctx.bumps.waterfall;
+            pool.has_waterfall = true;
+        }
+        waterfall.preferred_capital = preferred_capital;
+        waterfall.preferred_return_bps = preferred_return_bps;
+        waterfall.catch_up_bps = catch_up_bps;
+        waterfall.residual_preferred_bps = residual_preferred_bps;
+        
+        emit!(WaterfallConfigured {
+            pool: pool.key(),
+            preferred_pool: waterfall.preferred_pool,
+            preferred_mint: waterfall.preferred_mint,
+            preferred_capital,
+            preferred_return_bps,
+            catch_up_bps,
+            residual_preferred_bps,
+        });
+        
+        Ok(())
+    }
+
+    /// Permissionless: move the preferred class's pending allocation from this
+    /// pool's vault into the preferred pool, where it is distributed to preferred
+    /// holders like any deposit
+    pub fn transfer_preferred_allocation(ctx: Context<TransferPreferredAllocation>) -> Result<()> {
+        let waterfall = &mut ctx.accounts.waterfall;
+        let amount = waterfall.preferred_pending;
+        require!(amount > 0, DividendError::NoDividendsToDistribute);
+        
+        let preferred_pool = &mut ctx.accounts.preferred_pool;
+        require!(!preferred_pool.streaming, DividendError::WrongPoolMode);
+        
+        let pool = &ctx.accounts.dividend_pool;
+        let token_payout = TokenPayout::for_pool(
+            pool,
+            ctx.accounts.token_vault.as_ref(),
+            ctx.accounts.preferred_token_vault.as_ref(),
+            ctx.accounts.token_program.as_ref(),
+        )?;
+        pay_from_vault(
+            pool.key(),
+            &ctx.accounts.dividend_vault,
+            ctx.bumps.dividend_vault,
+            &ctx.accounts.preferred_vault,
+            token_payout,
+            &ctx.accounts.system_program,
+            amount,
+        )?;
+        
+        waterfall.preferred_pending = 0;
+        preferred_pool.total_deposited_current_epoch = preferred_pool
+            .total_deposited_current_epoch
+            .checked_add(amount)
+            .ok_or(DividendError::Overflow)?;
+        
+        emit!(PreferredAllocationTransferred {
+            pool: pool.key(),
+            preferred_pool: preferred_pool.key(),
+            amount,
+        });
+        
+        Ok(())
+    }
+
+    /// Set the management fee and capex reserve, in basis points of each
+    /// distribution's new deposits, and the `manager` wallet paid the fee
+    pub fn set_deductions(
+        ctx: Context<SetDeductions>,
+        management_fee_bps: u16,
+        reserve_bps: u16,
+        manager: Pubkey,
+    ) -> Result<()> {
+        require!(
+            management_fee_bps as u32 + reserve_bps as u32 <= 10_000,
+            DividendError::InvalidDeductions
+        );
+        
+        let pool = &mut ctx.accounts.dividend_pool;
+        pool.management_fee_bps = management_fee_bps;
+        pool.reserve_bps = reserve_bps;
+        pool.manager = manager;
+        
+        emit!(DeductionsUpdated {
+            pool: pool.key(),
+            management_fee_bps,
+            reserve_bps,
+            manager,
+        });
+        
+        Ok(())
+    }
+
+    /// Create the token account that accrues the reserve of an SPL pool
+    pub fn initialize_reserve_token_vault(ctx: Context<InitializeReserveTokenVault>) -> Result<()> {
+        require!(
+            ctx.accounts.dividend_pool.payout_mint.is_some(),
+            DividendError::WrongPoolMode
+        );
+        Ok(())
+    }
+
+    /// Release `amount` from the reserve, e.g. to pay for capital works. SOL goes to
+    /// `destination`; SPL pools pay into `destination_token_account`. The
+    /// `justification_hash` commits to the off-chain record of why it was released.
+    pub fn release_reserve(
+        ctx: Context<ReleaseReserve>,
+        amount: u64,
+        justification_hash: [u8; 32],
+    ) -> Result<()> {
+        require!(amount > 0, DividendError::InvalidAmount);
+        require!(justification_hash != [0u8; 32], DividendError::MissingJustification);
+        
+        let pool = &mut ctx.accounts.dividend_pool;
+        pool.reserve_balance = pool
+            .reserve_balance
+            .checked_sub(amount)
+            .ok_or(DividendError::InsufficientReserve)?;
+        
+        let pool_key = pool.key();
+        let seeds = &[b"reserve_vault", pool_key.as_ref(), &[ctx.bumps.reserve_vault]];
+        let signer_seeds = &[&seeds[..]];
+        
+        match (
+            pool.payout_mint,
+            &ctx.accounts.reserve_token_vault,
+            &ctx.accounts.destination_token_account,
+            &ctx.accounts.token_program,
+        ) {
+            (None, None, None, _) => anchor_lang::system_program::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.reserve_vault.to_account_info(),
+                        to: ctx.accounts.destination.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                amount,
+            )?,
+            (Some(_), Some(reserve_token_vault), Some(destination), Some(token_program)) => token::transfer(
+                CpiContext::new_with_signer(
+                    token_program.to_account_info(),
+                    Transfer {
+                        from: reserve_token_vault.to_account_info(),
+                        to: destination.to_account_info(),
+                        authority: ctx.accounts.reserve_vault.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                amount,
+            )?,
+            _ => return err!(DividendError::InvalidPayoutAccounts),
+        }
+        
+        emit!(ReserveReleased {
+            pool: pool_key,
+            destination: ctx.accounts.destination.key(),
+            amount,
+            justification_hash,
+        });
+        
+        Ok(())
+    }
+
+    /// Register a token account whose balance is not circulating supply. With no
+    /// `beneficiary` the balance is left out of every distribution's denominator;
+    /// otherwise it stays in and `beneficiary` claims its owner's share.
+    pub fn register_excluded_account(
+        ctx: Context<RegisterExcludedAccount>,
+        beneficiary: Option<Pubkey>,
+    ) -> Result<()> {
+        let pool = &mut ctx.accounts.dividend_pool;
+        let token_account = &ctx.accounts.token_account;
+        
+        let excluded = &mut ctx.accounts.excluded_account;
+        excluded.pool = pool.key();
+        excluded.token_account = token_account.key();
+        excluded.owner = token_account.owner;
+        excluded.beneficiary = beneficiary;
+        excluded.registered_at = Clock::get()?.unix_timestamp;
+        excluded.bump = ctx.bumps.excluded_account;
+        
+        // Only `Exclude` accounts have to be passed to the crank, so only they count
+        if beneficiary.is_none() {
+            require!(
+                pool.excluded_account_count < MAX_EXCLUDED_ACCOUNTS,
+                DividendError::TooManyExcludedAccounts
+            );
+            pool.excluded_account_count += 1;
+        }
+        
+        emit!(ExcludedAccountRegistered {
+            pool: pool.key(),
+            token_account: excluded.token_account,
+            owner: excluded.owner,
+            beneficiary,
+        });
+        
+        Ok(())
+    }
+
+    /// Register a marketplace listing's escrow so its share is redirected to the seller
+    pub fn register_listing_escrow(ctx: Context<RegisterListingEscrow>) -> Result<()> {
+        let pool = &mut ctx.accounts.dividend_pool;
+        let seller = ctx.accounts.listing.seller;
+        
+        let excluded = &mut ctx.accounts.excluded_account;
+        excluded.pool = pool.key();
+        excluded.token_account = ctx.accounts.escrow_token_account.key();
+        excluded.owner = ctx.accounts.listing.key();
+        excluded.beneficiary = Some(seller);
+        excluded.registered_at = Clock::get()?.unix_timestamp;
+        excluded.bump = ctx.bumps.excluded_account;
+        
+        emit!(ExcludedAccountRegistered {
+            pool: pool.key(),
+            token_account: excluded.token_account,
+            owner: excluded.owner,
+            beneficiary: Some(seller),
+        });
+        
+        Ok(())
+    }
+
+    /// Remove an excluded or redirected account, returning it to normal circulation
+    pub fn remove_excluded_account(ctx: Context<RemoveExcludedAccount>) -> Result<()> {
+        let pool = &mut ctx.accounts.dividend_pool;
+        if ctx.accounts.excluded_account.beneficiary.is_none() {
+            pool.excluded_account_count = pool
+                .excluded_account_count
+                .checked_sub(1)
+                .ok_or(DividendError::Overflow)?;
+        }
+        
+        emit!(ExcludedAccountRemoved {
+            pool: pool.key(),
+            token_account: ctx.accounts.excluded_account.token_account,
+        });
+        
+        Ok(())
+    }
+
+    /// Update pool authority (transfer ownership)
+    pub fn update_authority(ctx: Context<UpdateAuthority>, new_authority: Pubkey) -> Result<()> {
+        let pool = &mut ctx.accounts.dividend_pool;
+        
+        emit!(AuthorityUpdated {
+            pool: pool.key(),
+            old_authority: pool.authority,
+            new_authority,
+        });
+        
+        pool.authority = new_authority;
+        
+        Ok(())
+    }
+
+    /// Platform admin override: reassign the authority of a pool that was created
+    /// by someone other than the property's issuer
+    pub fn reassign_pool_authority(
+        ctx: Context<ReassignPoolAuthority>,
+        new_authority: Pubkey,
+    ) -> Result<()> {
+        require_keys_eq!(
+            load_platform_admin(&ctx.accounts.platform_config)?,
+            ctx.accounts.admin.key(),
+            DividendError::Unauthorized
+        );
+        
+        let pool = &mut ctx.accounts.dividend_pool;
+        
+        emit!(PoolAuthorityReassigned {
+            pool: pool.key(),
+            old_authority: pool.authority,
+            new_authority,
+            admin: ctx.accounts.admin.key(),
+        });
+        
+        pool.authority = new_authority;
+        
+        Ok(())
+    }
+
+    /// Migrate a pool created before the pool layout grew. The account is grown to
+    /// the current layout, the authority paying the extra rent. Settings added since
+    /// take the defaults of `initialize_pool`, and the tax and reserve vaults are
+    /// funded as for a new SOL pool. The dividend vault gets a rent reserve on top
+    /// of what it holds, all of which is owed to holders.
+    pub fn migrate_pool(ctx: Context<MigratePool>) -> Result<()> {
+        let pool_info = ctx.accounts.dividend_pool.to_account_info();
+        let legacy = load_legacy_account::<LegacyDividendPool>(
+            &pool_info,
+            LEGACY_POOL_SPACE,
+            &DividendPool::DISCRIMINATOR,
+        )?;
+        require_keys_eq!(legacy.authority, ctx.accounts.authority.key(), DividendError::Unauthorized);
+        
+        grow_account(
+            &ctx.accounts.authority,
+            &pool_info,
+            &ctx.accounts.system_program,
+            8 + 32 + 32 + 32 + 4 + 64 + 8 + 8 + 8 + 8 + 8 + 1 + 4 + 33 + 8 + 1 + 32 + 1 + 32 + 2 + 2 + 32 + 8 + 8 + 1 + 64,
+        )?;
+        let pool = DividendPool {
+            authority: legacy.authority,
+            property_mint: legacy.property_mint,
+            dividend_vault: legacy.dividend_vault,
+            property_id: legacy.property_id,
+            total_distributed: legacy.total_distributed,
+            current_epoch: legacy.current_epoch,
+            distribution_frequency_days: legacy.distribution_frequency_days,
+            last_distribution_time: legacy.last_distribution_time,
+            total_deposited_current_epoch: legacy.total_deposited_current_epoch,
+            bump: legacy.bump,
+            claim_period_days: DEFAULT_CLAIM_PERIOD_DAYS,
+            compliance_authority: legacy.authority,
+            manager: legacy.authority,
+            ..Default::default()
+        };
+        pool.try_serialize(&mut &mut pool_info.try_borrow_mut_data()?[..])?;
+        
+        // Every lamport already in the dividend vault is owed to holders, so its
+        // rent reserve is added on top rather than topped up
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.authority.to_account_info(),
+                    to: ctx.accounts.dividend_vault.to_account_info(),
+                },
+            ),
+            Rent::get()?.minimum_balance(0),
+        )?;
+        for vault in [&ctx.accounts.tax_vault, &ctx.accounts.reserve_vault] {
+            fund_rent_exempt(&ctx.accounts.authority, vault, &ctx.accounts.system_program)?;
+        }
+        
+        emit!(PoolMigrated {
+            pool: pool_info.key(),
+            authority: pool.authority,
+        });
+        
+        Ok(())
+    }
+
+    /// Migrate a distribution created before the distribution layout grew. Its
+    /// per-token amount is converted to the fixed-point scale. `claim_count` is the
+    /// number of claim records made against it, which `recompute_total_claimed`
+    /// relies on; take it from an indexer. The legacy `total_claimed` is kept as a
+    /// floor that the recompute cannot go below.
+    ///
+    /// The claim period starts at migration, so the remainder can be swept once it
+    /// ends. Holders claim after the authority posts a snapshot of balances at
+    /// `distributed_at` with `post_snapshot_root`; those who claimed before the
+    /// upgrade already have a claim record and cannot claim again.
+    pub fn migrate_distribution(
+        ctx: Context<MigrateDistribution>,
+        epoch: u64,
+        claim_count: u32,
+    ) -> Result<()> {
+        let distribution_info = ctx.accounts.distribution_record.to_account_info();
+        let claim_deadline = ctx
+            .accounts
+            .dividend_pool
+            .claim_deadline(Clock::get()?.unix_timestamp)?;
+        let legacy = load_legacy_account::<LegacyDistributionRecord>(
+            &distribution_info,
+            LEGACY_DISTRIBUTION_SPACE,
+            &DistributionRecord::DISCRIMINATOR,
+        )?;
+        require_keys_eq!(legacy.pool, ctx.accounts.dividend_pool.key(), DividendError::NotLegacyAccount);
+        require!(legacy.epoch == epoch, DividendError::NotLegacyAccount);
+        
+        grow_account(
+            &ctx.accounts.authority,
+            &distribution_info,
+            &ctx.accounts.system_program,
+            8 + 32 + 8 + 8 + 8 + 16 + 8 + 8 + 1 + 8 + 32 + 1 + 4 + 8 + 8 + 1 + 1 + 9 + 4 + 8 + 32,
+        )?;
+        let distribution = DistributionRecord {
+            pool: legacy.pool,
+            epoch: legacy.epoch,
+            total_amount: legacy.total_amount,
+            total_token_supply: legacy.total_token_supply,
+            amount_per_token: (legacy.amount_per_token as u128)
+                .checked_mul(AMOUNT_PER_TOKEN_SCALE)
+                .ok_or(DividendError::Overflow)?,
+            distributed_at: legacy.distributed_at,
+            total_claimed: legacy.total_claimed,
+            bump: legacy.bump,
+            mode: DistributionMode::BalanceSnapshot,
+            claim_deadline,
+            claim_count,
+            ..Default::default()
+        };
+        distribution.try_serialize(&mut &mut distribution_info.try_borrow_mut_data()?[..])?;
+        
+        emit!(DistributionMigrated {
+            pool: distribution.pool,
+            epoch,
+            amount_per_token: distribution.amount_per_token,
+            claim_count,
+        });
+        
+        Ok(())
+    }
+}
+
+/// Dividend owed on `balance` tokens at a fixed-point `amount_per_token`, rounded down
+pub fn entitlement(balance: u64, amount_per_token: u128) -> Result<u64> {
+    let amount = (balance as u128)
+        .checked_mul(amount_per_token)
+        .ok_or(DividendError::Overflow)?
+        / AMOUNT_PER_TOKEN_SCALE;
+    u64::try_from(amount).map_err(|_| error!(DividendError::Overflow))
+}
+
+/// Amount `holder` can claim now from a balance-snapshot distribution, after checking
+/// the claim period is open and `proof` matches their snapshot balance
+fn snapshot_claim_amount(
+    distribution: &DistributionRecord,
+    holder: &Pubkey,
+    snapshot_balance: u64,
+    proof: &[[u8; 32]],
+) -> Result<u64> {
+    require!(snapshot_balance > 0, DividendError::NoTokensHeld);
+    require!(!distribution.voided, DividendError::DistributionVoided);
+    require!(
+        distribution.mode == DistributionMode::BalanceSnapshot,
+        DividendError::WrongDistributionMode
+    );
+    require!(
+        !distribution.is_expired(Clock::get()?.unix_timestamp),
+        DividendError::ClaimExpired
+    );
+    require!(
+        distribution.snapshot_root != [0u8; 32],
+        DividendError::SnapshotPending
+    );
+    require!(
+        verify_proof(
+            &distribution.snapshot_root,
+            balance_leaf(holder, snapshot_balance),
+            proof,
+        ),
+        DividendError::InvalidSnapshotProof
+    );
+    
+    let raw = entitlement(snapshot_balance, distribution.amount_per_token)?;
+    let remaining = distribution
+        .total_amount
+        .saturating_sub(distribution.total_claimed);
+    let amount = raw.min(remaining);
+    require!(amount > 0, DividendError::NoDividendsToClaim);
+    Ok(amount)
+}
+
+/// Withholding rate of a holder's tax profile, which may not exist
+fn withholding_bps(tax_profile: &AccountInfo) -> Result<u16> {
+    if tax_profile.data_is_empty() {
+        return Ok(0);
+    }
+    let data = tax_profile.try_borrow_data()?;
+    Ok(TaxProfile::try_deserialize(&mut &data[..])?.withholding_bps)
+}
+
+/// `bps` basis points of `amount`, rounded down
+fn bps_of(amount: u64, bps: u16) -> Result<u64> {
+    let share = (amount as u128)
+        .checked_mul(bps as u128)
+        .ok_or(DividendError::Overflow)?
+        / 10_000;
+    u64::try_from(share).map_err(|_| error!(DividendError::Overflow))
+}
+
+/// Wallet a claim pays: the holder's payout address if they set a preference,
+/// otherwise the signer
+fn payout_recipient(user: Pubkey, preference: Option<&Account<PayoutPreference>>) -> Pubkey {
+    preference.map_or(user, |preference| preference.payout_address)
+}
+
+/// Account receiving a SOL claim for `recipient`: the passed payout address, or the
+/// signer when none is passed
+fn payout_account<'info>(
+    user: &Signer<'info>,
+    payout_address: Option<&UncheckedAccount<'info>>,
+    recipient: Pubkey,
+) -> Result<AccountInfo<'info>> {
+    let account = match payout_address {
+        Some(payout_address) => payout_address.to_account_info(),
+        None => user.to_account_info(),
+    };
+    require_keys_eq!(account.key(), recipient, DividendError::InvalidPayoutAddress);
+    Ok(account)
+}
+
+/// Check `user` may claim for `holder`: as the holder, a redirect beneficiary, or the
+/// holder's delegate: they are the same wallet, or `redirect`
+/// names `user` as beneficiary of `holder`'s share
+fn authorize_claim(
+    holder: Pubkey,
+    user: Pubkey,
+    redirect: Option<&Account<ExcludedAccount>>,
+    preference: Option<&Account<PayoutPreference>>,
+) -> Result<()> {
+    let delegate = preference.and_then(|preference| preference.delegate);
+    if holder != user && delegate != Some(user) {
+        let redirect = redirect.ok_or(DividendError::Unauthorized)?;
+        require!(
+            redirect.owner == holder && redirect.beneficiary == Some(user),
+            DividendError::Unauthorized
+        );
+    }
+    Ok(())
+}
+
+/// Property tokens `owner` holds in their associated token account `info`, which
+/// may not exist. An account handed to another owner counts as empty.
+fn holder_balance(info: &AccountInfo, owner: &Pubkey, mint: &Pubkey) -> Result<u64> {
+    if info.data_is_empty() || info.owner != &Token::id() {
+        return Ok(0);
+    }
+    let data = info.try_borrow_data()?;
+    let account = TokenAccount::try_deserialize(&mut &data[..])?;
+    if account.owner == *owner && account.mint == *mint {
+        Ok(account.amount)
+    } else {
+        Ok(0)
+    }
+}
+
+/// A holder cursor that may not exist
+fn load_cursor(info: &AccountInfo) -> Result<Option<HolderCursor>> {
+    if info.data_is_empty() {
+        return Ok(None);
+    }
+    let data = info.try_borrow_data()?;
+    Ok(Some(HolderCursor::try_deserialize(&mut &data[..])?))
+}
+
+/// Wallet a pushed dividend pays, from a `push_dividends` entry's preference and
+/// recipient accounts, with the recipient's token account for SPL pools
+fn push_recipient<'info>(
+    pool: &DividendPool,
+    holder: Pubkey,
+    accounts: &'info [AccountInfo<'info>],
+) -> Result<(Pubkey, Option<Account<'info, TokenAccount>>)> {
+    let recipient = load_payout_preference(&accounts[4])?
+        .map_or(holder, |preference| preference.payout_address);
+    match pool.payout_mint {
+        Some(payout_mint) => {
+            let token_account = Account::<TokenAccount>::try_from(&accounts[5])?;
+            require!(token_account.mint == payout_mint, DividendError::InvalidMint);
+            require_keys_eq!(token_account.owner, recipient, DividendError::InvalidTokenOwner);
+            Ok((recipient, Some(token_account)))
+        }
+        None => {
+            require_keys_eq!(accounts[5].key(), recipient, DividendError::InvalidPayoutAddress);
+            Ok((recipient, None))
+        }
+    }
+}
+
+/// A holder's payout preference, which may not exist
+fn load_payout_preference(info: &AccountInfo) -> Result<Option<PayoutPreference>> {
+    if info.data_is_empty() {
+        return Ok(None);
+    }
+    let data = info.try_borrow_data()?;
+    Ok(Some(PayoutPreference::try_deserialize(&mut &data[..])?))
+}
+
+/// Whether range claims settled `epoch`, from a holder cursor that may not exist
+fn cursor_settles(info: &AccountInfo, epoch: u64) -> Result<bool> {
+    Ok(load_cursor(info)?.is_some_and(|cursor| cursor.settles(epoch)))
+}
+
+/// Settle a claim of `amount` for `epoch` on the holder's range-claim cursor, which
+/// may not exist, instead of in a `ClaimRecord`. Only a cursor standing at `epoch`
+/// settles it, and only when `amount` is the epoch's full entitlement, since the
+/// cursor records no per-epoch amount, and only a cursor passed writable.
+/// Returns whether the cursor moved.
+fn settle_on_cursor(
+    info: &AccountInfo,
+    distribution: &mut DistributionRecord,
+    epoch: u64,
+    snapshot_balance: u64,
+    amount: u64,
+) -> Result<bool> {
+    let Some(mut cursor) = load_cursor(info)? else {
+        return Ok(false);
+    };
+    if !info.is_writable
+        || cursor.next_epoch != epoch
+        || amount != entitlement(snapshot_balance, distribution.amount_per_token)?
+    {
+        return Ok(false);
+    }
+    
+    cursor.next_epoch = epoch + 1;
+    cursor.total_claimed = cursor
+        .total_claimed
+        .checked_add(amount)
+        .ok_or(DividendError::Overflow)?;
+    distribution.range_claimed = distribution
+        .range_claimed
+        .checked_add(amount)
+        .ok_or(DividendError::Overflow)?;
+    let mut data = info.try_borrow_mut_data()?;
+    cursor.try_serialize(&mut &mut data[..])?;
+    Ok(true)
+}
+
+/// Amount recorded by a claim record that may not exist
+fn claim_record_amount(info: &AccountInfo) -> Result<Option<u64>> {
+    if info.data_is_empty() {
+        return Ok(None);
+    }
+    let data = info.try_borrow_data()?;
+    Ok(Some(ClaimRecord::try_deserialize(&mut &data[..])?.amount_claimed))
+}
+
+/// Create a `ClaimRecord` at an address passed in `remaining_accounts`; `seeds`
+/// are its PDA seeds, bump included. The address may already hold lamports, so
+/// rent is topped up and the account allocated and assigned in separate steps.
+fn create_claim_record<'info>(
+    payer: &Signer<'info>,
+    info: &AccountInfo<'info>,
+    seeds: &[&[u8]],
+    system_program: &Program<'info, System>,
+    record: &ClaimRecord,
+) -> Result<()> {
+    let space = 8 + 32 + 32 + 8 + 8 + 8 + 1 + 1 + 8 + 8 + 8 + 8 + 32;
+    let shortfall = Rent::get()?
+        .minimum_balance(space)
+        .saturating_sub(info.lamports());
+    if shortfall > 0 {
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: payer.to_account_info(),
+                    to: info.clone(),
+                },
+            ),
+            shortfall,
+        )?;
+    }
+    anchor_lang::system_program::allocate(
+        CpiContext::new_with_signer(
+            system_program.to_account_info(),
+            anchor_lang::system_program::Allocate {
+                account_to_allocate: info.clone(),
+            },
+            &[seeds],
+        ),
+        space as u64,
+    )?;
+    anchor_lang::system_program::assign(
+        CpiContext::new_with_signer(
+            system_program.to_account_info(),
+            anchor_lang::system_program::Assign {
+                account_to_assign: info.clone(),
+            },
+            &[seeds],
+        ),
+        &crate::ID,
+    )?;
+    let mut data = info.try_borrow_mut_data()?;
+    record.try_serialize(&mut &mut data[..])
+}
+
+/// Load the distribution for `epoch` from a `[distribution_record, claim_record]`
+/// pair, checking `claim_record` is `holder`'s claim record address for it
+fn load_epoch_pair<'info>(
+    pair: &'info [AccountInfo<'info>],
+    pool: Pubkey,
+    epoch: u64,
+    holder: &Pubkey,
+) -> Result<Account<'info, DistributionRecord>> {
+    let distribution = Account::<DistributionRecord>::try_from(&pair[0])?;
+    require!(
+        distribution.pool == pool && distribution.epoch == epoch,
+        DividendError::InvalidClaimRange
+    );
+    let (claim_record, _) = Pubkey::find_program_address(
+        &[b"claim", pair[0].key.as_ref(), holder.as_ref()],
+        &crate::ID,
+    );
+    require_keys_eq!(pair[1].key(), claim_record, DividendError::InvalidClaimRange);
+    Ok(distribution)
+}
+
+/// What `holder` can claim now from a balance-snapshot distribution, what they
+/// have been paid from it, and what they forfeited. `claimed` is their
+/// `ClaimRecord` amount, if any; `settled` means a range claim moved their cursor
+/// past the epoch.
+fn claimable_in(
+    distribution: &DistributionRecord,
+    holder: &Pubkey,
+    snapshot_balance: u64,
+    proof: &[[u8; 32]],
+    claimed: Option<u64>,
+    settled: bool,
+    now: i64,
+) -> Result<ClaimableAmount> {
+    let mut result = ClaimableAmount {
+        claimable_now: 0,
+        already_claimed: claimed.unwrap_or(0),
+        claim_deadline: distribution.claim_deadline,
+        forfeited: 0,
+    };
+    if distribution.mode != DistributionMode::BalanceSnapshot
+        || distribution.snapshot_root == [0u8; 32]
+        || snapshot_balance == 0
+        || !verify_proof(
+            &distribution.snapshot_root,
+            balance_leaf(holder, snapshot_balance),
+            proof,
+        )
+        || distribution.voided
+    {
+        return Ok(result);
+    }
+    
+    let raw = entitlement(snapshot_balance, distribution.amount_per_token)?;
+    match claimed {
+        // Claims never pay zero, so a zero-amount record is a `skip_epochs` marker
+        Some(0) => result.forfeited = raw,
+        Some(_) => {}
+        // Range claims record no per-epoch amount, but only settle an epoch by
+        // paying its full entitlement
+        None if settled => result.already_claimed = raw,
+        None if distribution.is_expired(now) => result.forfeited = raw,
+        None => {
+            let remaining = distribution
+                .total_amount
+                .saturating_sub(distribution.total_claimed);
+            result.claimable_now = raw.min(remaining);
+        }
+    }
+    Ok(result)
+}
+
+/// Record `amount` of a common distribution returned to `pool`'s deposits on the
+/// pool's waterfall, which must be passed when the pool has one
+fn return_to_waterfall(
+    pool: &DividendPool,
+    waterfall: Option<&mut Account<Waterfall>>,
+    amount: u64,
+) -> Result<()> {
+    require!(
+        waterfall.is_some() == pool.has_waterfall,
+        DividendError::WaterfallMismatch
+    );
+    match waterfall {
+        Some(waterfall) => waterfall.return_common(amount),
+        None => Ok(()),
+    }
+}
+
+/// Management fee and reserve taken from a distribution's deposits
+struct Deductions {
+    management_fee: u64,
+    reserve: u64,
+}
+
+/// Open the pool's next balance-snapshot distribution over its undistributed
+/// deposits, less the management fee and reserve, which the caller pays out, and
+/// the preferred class's allocation when the pool has a waterfall. `distribution`
+/// must be the freshly created record for the current epoch, with its snapshot set;
+/// `total_supply` is the snapshot's total balance and `excluded_supply` the supply
+/// left out of it.
+fn open_distribution(
+    pool: &mut Account<DividendPool>,
+    distribution: &mut Account<DistributionRecord>,
+    mut waterfall: Option<&mut Account<Waterfall>>,
+    total_supply: u64,
+    excluded_supply: u64,
+    started_by: Pubkey,
+) -> Result<Deductions> {
+    let clock = Clock::get()?;
+    
+    require!(!pool.streaming, DividendError::WrongPoolMode);
+    require!(
+        pool.total_deposited_current_epoch > 0,
+        DividendError::NoDividendsToDistribute
+    );
+    require!(total_supply > 0, DividendError::NoTokensInCirculation);
+    
+    let deductions = pool.take_deductions()?;
+    let available = pool.total_deposited_current_epoch;
+    require!(
+        waterfall.is_some() == pool.has_waterfall,
+        DividendError::WaterfallMismatch
+    );
+    let preferred_amount = match waterfall.as_mut() {
+        Some(waterfall) => waterfall.split(available, clock.unix_timestamp)?,
+        None => 0,
+    };
+    
+    // Per-token amount in fixed point; whatever the rounding leaves behind
+    // carries over into the next epoch instead of being stranded in the vault
+    let deposited = available - preferred_amount;
+    let amount_per_token = (deposited as u128)
+        .checked_mul(AMOUNT_PER_TOKEN_SCALE)
+        .ok_or(DividendError::Overflow)?
+        .checked_div(total_supply as u128)
+        .ok_or(DividendError::Overflow)?;
+    let distributable = entitlement(total_supply, amount_per_token)?;
+    require!(
+        distributable > 0 || preferred_amount > 0,
+        DividendError::NoDividendsToDistribute
+    );
+    let remainder = deposited
+        .checked_sub(distributable)
+        .ok_or(DividendError::Overflow)?;
+    if let Some(waterfall) = waterfall {
+        waterfall.return_common(remainder)?;
+    }
+    
+    distribution.pool = pool.key();
+    distribution.epoch = pool.current_epoch;
+    distribution.total_amount = distributable;
+    distribution.total_token_supply = total_supply;
+    distribution.amount_per_token = amount_per_token;
+    distribution.distributed_at = clock.unix_timestamp;
+    distribution.total_claimed = 0;
+    distribution.mode = DistributionMode::BalanceSnapshot;
+    distribution.holder_count = 0;
+    distribution.excluded_supply = excluded_supply;
+    distribution.claim_deadline = pool.claim_deadline(clock.unix_timestamp)?;
+    distribution.swept = false;
+    distribution.voided = false;
+    distribution.corrects_epoch = None;
+    distribution.claim_count = 0;
+    distribution.range_claimed = 0;
+    
+    pool.total_distributed = pool
+        .total_distributed
+        .checked_add(distributable)
+        .ok_or(DividendError::Overflow)?;
+    pool.last_distribution_time = clock.unix_timestamp;
+    pool.current_epoch = pool.current_epoch.checked_add(1).ok_or(DividendError::Overflow)?;
+    pool.total_deposited_current_epoch = remainder;
+    
+    emit!(DistributionStarted {
+        pool: pool.key(),
+        epoch: distribution.epoch,
+        total_amount: distribution.total_amount,
+        amount_per_token,
+        snapshot_slot: distribution.snapshot_slot,
+        snapshot_root: distribution.snapshot_root,
+        carried_forward: remainder,
+        excluded_supply,
+        started_by,
+        management_fee: deductions.management_fee,
+        reserve: deductions.reserve,
+        preferred_amount,
+    });
+    
+    Ok(deductions)
+}
+
+/// Accounts that receive a distribution's management fee and reserve
+struct DeductionPayees<'a, 'info> {
+    dividend_vault: &'a AccountInfo<'info>,
+    vault_bump: u8,
+    manager: &'a AccountInfo<'info>,
+    reserve_vault: &'a AccountInfo<'info>,
+    token_vault: Option<&'a Account<'info, TokenAccount>>,
+    manager_token_account: Option<&'a Account<'info, TokenAccount>>,
+    reserve_token_vault: Option<&'a Account<'info, TokenAccount>>,
+    token_program: Option<&'a Program<'info, Token>>,
+    system_program: &'a Program<'info, System>,
+}
+
+/// Pay the management fee to the manager and the reserve to the reserve vault
+fn pay_deductions<'info>(
+    pool: &Account<'info, DividendPool>,
+    deductions: &Deductions,
+    payees: DeductionPayees<'_, 'info>,
+) -> Result<()> {
+    let transfers = [
+        (deductions.management_fee, payees.manager, payees.manager_token_account),
+        (deductions.reserve, payees.reserve_vault, payees.reserve_token_vault),
+    ];
+    for (amount, recipient, destination) in transfers {
+        if amount == 0 {
+            continue;
+        }
+        let token_payout = TokenPayout::for_pool(
+            pool,
+            payees.token_vault,
+            destination,
+            payees.token_program,
+        )?;
+        pay_from_vault(
+            pool.key(),
+            payees.dividend_vault,
+            payees.vault_bump,
+            recipient,
+            token_payout,
+            payees.system_program,
+            amount,
+        )?;
+    }
+    Ok(())
+}
+
+/// Token accounts that move an SPL pool's payout mint out of its vault
+pub struct TokenPayout<'a, 'info> {
+    vault: &'a Account<'info, TokenAccount>,
+    destination: &'a Account<'info, TokenAccount>,
+    token_program: &'a Program<'info, Token>,
+}
+
+impl<'a, 'info> TokenPayout<'a, 'info> {
+    /// Token accounts for `pool`'s payout mint; `None` for SOL pools
+    fn for_pool(
+        pool: &DividendPool,
+        vault: Option<&'a Account<'info, TokenAccount>>,
+        destination: Option<&'a Account<'info, TokenAccount>>,
+        token_program: Option<&'a Program<'info, Token>>,
+    ) -> Result<Option<Self>> {
+        match (pool.payout_mint, vault, destination, token_program) {
+            (None, None, None, _) => Ok(None),
+            (Some(_), Some(vault), Some(destination), Some(token_program)) => Ok(Some(Self {
+                vault,
+                destination,
+                token_program,
+            })),
+            _ => err!(DividendError::InvalidPayoutAccounts),
+        }
+    }
+}
+
+/// Pay `amount` out of the pool's vault: lamports to `recipient` for SOL pools, or
+/// payout tokens to the `token_payout` destination for SPL pools
+fn pay_from_vault<'info>(
+    pool_key: Pubkey,
+    dividend_vault: &AccountInfo<'info>,
+    vault_bump: u8,
+    recipient: &AccountInfo<'info>,
+    token_payout: Option<TokenPayout<'_, 'info>>,
+    system_program: &Program<'info, System>,
+    amount: u64,
+) -> Result<()> {
+    let seeds = &[b"dividend_vault".as_ref(), pool_key.as_ref(), &[vault_bump]];
+    let signer_seeds = &[&seeds[..]];
+    
+    match token_payout {
+        Some(token_payout) => token::transfer(
+            CpiContext::new_with_signer(
+                token_payout.token_program.to_account_info(),
+                Transfer {
+                    from: token_payout.vault.to_account_info(),
+                    to: token_payout.destination.to_account_info(),
+                    authority: dividend_vault.clone(),
+                },
+                signer_seeds,
+            ),
+            amount,
+        ),
+        None => anchor_lang::system_program::transfer(
+            CpiContext::new_with_signer(
+                system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: dividend_vault.clone(),
+                    to: recipient.clone(),
+                },
+                signer_seeds,
+            ),
+            amount,
+        ),
+    }
+}
+
+/// Top a system-owned vault PDA up to the rent-exempt minimum of an empty account,
+/// so transfers into it of less than that amount do not fail
+fn fund_rent_exempt<'info>(
+    payer: &AccountInfo<'info>,
+    vault: &AccountInfo<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    let shortfall = Rent::get()?.minimum_balance(0).saturating_sub(vault.lamports());
+    if shortfall > 0 {
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: payer.clone(),
+                    to: vault.clone(),
+                },
+            ),
+            shortfall,
+        )?;
+    }
+    Ok(())
+}
+
+/// Lamports a system-owned vault PDA can pay out and stay rent-exempt
+fn lamports_above_rent(vault: &AccountInfo) -> Result<u64> {
+    Ok(vault.lamports().saturating_sub(Rent::get()?.minimum_balance(0)))
+}
+
+/// Extra pool seed for an optional payout mint; empty for SOL pools
+fn payout_mint_seed<'info>(payout_mint: &Option<Account<'info, Mint>>) -> &'info [u8] {
+    payout_mint
+        .as_ref()
+        .map_or(&[], |mint| mint.to_account_info().key.as_ref())
+}
+
+/// Total balance of the pool's `Exclude` accounts, from `[excluded_account,
+/// token_account]` pairs that must cover every one of them exactly once
+fn excluded_supply<'info>(
+    pool: &Account<'info, DividendPool>,
+    accounts: &'info [AccountInfo<'info>],
+) -> Result<u64> {
+    let pairs = accounts.chunks_exact(2);
+    require!(
+        pairs.remainder().is_empty() && pairs.len() == pool.excluded_account_count as usize,
+        DividendError::ExcludedAccountsMismatch
+    );
+    
+    let mut previous: Option<Pubkey> = None;
+    let mut total: u64 = 0;
+    for pair in pairs {
+        // Strictly ascending addresses rule out passing one account twice
+        if let Some(previous) = previous {
+            require!(pair[0].key() > previous, DividendError::ExcludedAccountsMismatch);
+        }
+        previous = Some(pair[0].key());
+        
+        let excluded = Account::<ExcludedAccount>::try_from(&pair[0])?;
+        require_keys_eq!(excluded.pool, pool.key(), DividendError::ExcludedAccountsMismatch);
+        require_keys_eq!(pair[1].key(), excluded.token_account, DividendError::ExcludedAccountsMismatch);
+        require!(excluded.beneficiary.is_none(), DividendError::ExcludedAccountsMismatch);
+        
+        let token_account = Account::<TokenAccount>::try_from(&pair[1])?;
+        total = total
+            .checked_add(token_account.amount)
+            .ok_or(DividendError::Overflow)?;
+    }
+    
+    Ok(total)
+}
+
+// ============================================================================
+// Crowdfunding Accounts
+// ============================================================================
+
+// `crowdfunding` depends on this crate for CPI, so its accounts are read here
+// through local mirrors of their leading fields.
+
+const CROWDFUNDING_STATUS_FUNDED: u8 = 1;
+
+/// Leading fields of `crowdfunding::Campaign`
+#[derive(AnchorDeserialize)]
+pub struct CrowdfundingCampaign {
+    pub creator: Pubkey,
+    pub property_mint: Pubkey,
+    pub escrow_vault: Pubkey,
+    pub property_id: String,
+    pub funding_goal: u64,
+    pub total_raised: u64,
+    pub platform_equity_bps: u16,
+    pub funding_deadline: i64,
+    pub token_price: u64,
+    pub total_tokens: u64,
+    pub tokens_sold: u64,
+    pub investor_count: u32,
+    pub status: u8,
+}
+
+/// Leading fields of `crowdfunding::PlatformConfig`
+#[derive(AnchorDeserialize)]
+pub struct CrowdfundingPlatformConfig {
+    pub admin: Pubkey,
+}
+
+/// Deserialize a crowdfunding account after checking its owner and discriminator
+fn load_crowdfunding_account<T: AnchorDeserialize>(info: &AccountInfo, name: &str) -> Result<T> {
+    require_keys_eq!(*info.owner, crowdfunding_program::ID, DividendError::InvalidCrowdfundingAccount);
+    
+    let data = info.try_borrow_data()?;
+    let discriminator = anchor_lang::solana_program::hash::hash(format!("account:{}", name).as_bytes());
+    require!(
+        data.len() >= 8 && data[..8] == discriminator.to_bytes()[..8],
+        DividendError::InvalidCrowdfundingAccount
+    );
+    
+    T::deserialize(&mut &data[8..]).map_err(|_| error!(DividendError::InvalidCrowdfundingAccount))
+}
+
+/// Admin recorded in the crowdfunding platform config PDA
+fn load_platform_admin(info: &AccountInfo) -> Result<Pubkey> {
+    let (expected, _) = Pubkey::find_program_address(&[b"platform_config"], &crowdfunding_program::ID);
+    require_keys_eq!(info.key(), expected, DividendError::InvalidCrowdfundingAccount);
+    
+    Ok(load_crowdfunding_account::<CrowdfundingPlatformConfig>(info, "PlatformConfig")?.admin)
+}
+
+// ============================================================================
+// Legacy Accounts
+// ============================================================================
+
+// Pools and distributions created before their layouts grew keep their
+// discriminators, but are too small to deserialize as the current types. The
+// migration instructions read them through these mirrors.
+
+/// Account size of a pool created before the pool layout grew
+pub const LEGACY_POOL_SPACE: usize = 8 + 32 + 32 + 32 + 4 + 64 + 8 + 8 + 8 + 8 + 8 + 1 + 64;
+
+/// Account size of a distribution created before the distribution layout grew
+pub const LEGACY_DISTRIBUTION_SPACE: usize = 8 + 32 + 8 + 8 + 8 + 8 + 8 + 8 + 1 + 32;
+
+/// `DividendPool` before its layout grew
+#[derive(AnchorDeserialize)]
+pub struct LegacyDividendPool {
+    pub authority: Pubkey,
+    pub property_mint: Pubkey,
+    pub dividend_vault: Pubkey,
+    pub property_id: String,
+    pub total_distributed: u64,
+    pub current_epoch: u64,
+    pub distribution_frequency_days: u64,
+    pub last_distribution_time: i64,
+    pub total_deposited_current_epoch: u64,
+    pub bump: u8,
+}
+
+/// `DistributionRecord` before its layout grew; `amount_per_token` is in whole
+/// lamports per token, without the fixed-point scale
+#[derive(AnchorDeserialize)]
+pub struct LegacyDistributionRecord {
+    pub pool: Pubkey,
+    pub epoch: u64,
+    pub total_amount: u64,
+    pub total_token_supply: u64,
+    pub amount_per_token: u64,
+    pub distributed_at: i64,
+    pub total_claimed: u64,
+    pub bump: u8,
+}
+
+/// Deserialize an account of this program still in its legacy layout, after
+/// checking its owner, size and discriminator
+fn load_legacy_account<T: AnchorDeserialize>(
+    info: &AccountInfo,
+    space: usize,
+    discriminator: &[u8],
+) -> Result<T> {
+    require_keys_eq!(*info.owner, crate::ID, DividendError::NotLegacyAccount);
+    
+    let data = info.try_borrow_data()?;
+    require!(
+        data.len() == space && data[..8] == *discriminator,
+        DividendError::NotLegacyAccount
+    );
+    
+    T::deserialize(&mut &data[8..]).map_err(|_| error!(DividendError::NotLegacyAccount))
+}
+
+/// Grow an account of this program to `space` bytes, `payer` topping up its rent
+fn grow_account<'info>(
+    payer: &AccountInfo<'info>,
+    account: &AccountInfo<'info>,
+    system_program: &Program<'info, System>,
+    space: usize,
+) -> Result<()> {
+    let shortfall = Rent::get()?
+        .minimum_balance(space)
+        .saturating_sub(account.lamports());
+    if shortfall > 0 {
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+            ),
+            shortfall,
+        )?;
+    }
+    account.realloc(space, true)?;
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[account]
+#[derive(Default)]
+pub struct DividendPool {
+    /// Authority who can deposit dividends and manage the pool
+    pub authority: Pubkey,
+    /// The property token mint
+    pub property_mint: Pubkey,
+    /// Vault holding SOL for dividends; also the authority of an SPL pool's token vault
+    pub dividend_vault: Pubkey,
+    /// Property identifier
+    pub property_id: String,
+    /// Total payout asset distributed all time
+    pub total_distributed: u64,
+    /// Current distribution epoch
+    pub current_epoch: u64,
     /// How often dividends are distributed (in days)
     pub distribution_frequency_days: u64,
     /// Last distribution timestamp
     pub last_distribution_time: i64,
-    /// SOL deposited in current epoch (not yet distributed)
+    /// Payout asset deposited in current epoch (not yet distributed)
     pub total_deposited_current_epoch: u64,
     /// PDA bump
     pub bump: u8,
+    /// Number of registered `ExcludedAccount`s without a beneficiary
+    pub excluded_account_count: u32,
+    /// Token paid out by this pool; `None` pays SOL
+    pub payout_mint: Option<Pubkey>,
+    /// Days each distribution stays claimable; `0` never expires
+    pub claim_period_days: u64,
+    /// What happens to a distribution's unclaimed remainder after its deadline
+    pub unclaimed_policy: UnclaimedPolicy,
+    /// Recipient of swept remainders under `UnclaimedPolicy::Treasury`
+    pub treasury: Pubkey,
+    /// Whether deposits are streamed to holders instead of distributed by epoch
+    pub streaming: bool,
+    /// Manages tax profiles and remits withheld tax
+    pub compliance_authority: Pubkey,
+    /// Management fee taken from each distribution's new deposits, in basis points
+    pub management_fee_bps: u16,
+    /// Share of each distribution's new deposits held back as reserve, in basis points
+    pub reserve_bps: u16,
+    /// Property manager paid the management fee
+    pub manager: Pubkey,
+    /// Reserve held in the reserve vault and not yet released
+    pub reserve_balance: u64,
+    /// Deposits since the last distribution, before deductions
+    pub deductible_deposits: u64,
+    /// Whether distributions go through a `Waterfall` with a preferred share class
+    pub has_waterfall: bool,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Default)]
+pub enum UnclaimedPolicy {
+    /// Add the remainder to the next epoch's deposits
+    #[default]
+    Rollover,
+    /// Pay the remainder to the pool treasury
+    Treasury,
+}
+
+impl DividendPool {
+    /// Extra pool seed: the payout mint for SPL pools, empty for SOL pools
+    pub fn payout_mint_seed(&self) -> &[u8] {
+        self.payout_mint.as_ref().map_or(&[], |mint| mint.as_ref())
+    }
+
+    /// Whether `distribution_frequency_days` have passed since the last distribution
+    pub fn distribution_due(&self, now: i64) -> Result<bool> {
+        if self.last_distribution_time == 0 {
+            return Ok(true);
+        }
+        let interval = i64::try_from(self.distribution_frequency_days)
+            .ok()
+            .and_then(|days| days.checked_mul(SECONDS_PER_DAY))
+            .ok_or(DividendError::Overflow)?;
+        let next = self
+            .last_distribution_time
+            .checked_add(interval)
+            .ok_or(DividendError::Overflow)?;
+        Ok(now >= next)
+    }
+
+    /// Add a deposit to the undistributed and deductible amounts
+    fn record_deposit(&mut self, amount: u64) -> Result<()> {
+        self.total_deposited_current_epoch = self
+            .total_deposited_current_epoch
+            .checked_add(amount)
+            .ok_or(DividendError::Overflow)?;
+        self.deductible_deposits = self
+            .deductible_deposits
+            .checked_add(amount)
+            .ok_or(DividendError::Overflow)?;
+        Ok(())
+    }
+    
+    /// Take the management fee and reserve out of the undistributed deposits and
+    /// add the reserve to `reserve_balance`; the caller pays both out
+    fn take_deductions(&mut self) -> Result<Deductions> {
+        // Deductions apply to new deposits only, not to amounts carried over
+        let deductible = self.deductible_deposits.min(self.total_deposited_current_epoch);
+        let management_fee = bps_of(deductible, self.management_fee_bps)?;
+        let reserve = bps_of(deductible, self.reserve_bps)?;
+        
+        self.total_deposited_current_epoch = self
+            .total_deposited_current_epoch
+            .checked_sub(management_fee)
+            .and_then(|amount| amount.checked_sub(reserve))
+            .ok_or(DividendError::Overflow)?;
+        self.deductible_deposits = 0;
+        self.reserve_balance = self
+            .reserve_balance
+            .checked_add(reserve)
+            .ok_or(DividendError::Overflow)?;
+        
+        Ok(Deductions { management_fee, reserve })
+    }
+    
+    /// Take the deductions, then `total_amount` for a merkle-amount distribution,
+    /// out of the undistributed deposits
+    fn fund_merkle_distribution(&mut self, total_amount: u64) -> Result<Deductions> {
+        let deductions = self.take_deductions()?;
+        self.total_deposited_current_epoch = self
+            .total_deposited_current_epoch
+            .checked_sub(total_amount)
+            .ok_or(DividendError::InsufficientDeposits)?;
+        Ok(deductions)
+    }
+    
+    /// Claim deadline of a distribution opened at `now`; `0` when claims never expire
+    pub fn claim_deadline(&self, now: i64) -> Result<i64> {
+        if self.claim_period_days == 0 {
+            return Ok(0);
+        }
+        i64::try_from(self.claim_period_days)
+            .ok()
+            .and_then(|days| days.checked_mul(SECONDS_PER_DAY))
+            .and_then(|period| now.checked_add(period))
+            .ok_or_else(|| error!(DividendError::Overflow))
+    }
+}
+
+#[account]
+#[derive(Default)]
+pub struct DistributionRecord {
+    /// The dividend pool
+    pub pool: Pubkey,
+    /// Distribution epoch number
+    pub epoch: u64,
+    /// Total payout asset distributed this epoch
+    pub total_amount: u64,
+    /// Circulating token supply at distribution time
+    pub total_token_supply: u64,
+    /// Payout amount per token, scaled by `AMOUNT_PER_TOKEN_SCALE`
+    pub amount_per_token: u128,
+    /// Timestamp of distribution
+    pub distributed_at: i64,
+    /// Total amount claimed so far
+    pub total_claimed: u64,
+    /// PDA bump
+    pub bump: u8,
+    /// Slot at which holder balances were snapshotted
+    pub snapshot_slot: u64,
+    /// Merkle root of holder balances (or amounts, in merkle-amount mode); zero
+    /// until posted for a cranked distribution
+    pub snapshot_root: [u8; 32],
+    /// How entitlements are proven
+    pub mode: DistributionMode,
+    /// Number of holders in a merkle-amount distribution
+    pub holder_count: u32,
+    /// Supply left out of `total_token_supply` when the distribution opened:
+    /// excluded holdings, plus anything minted after an earlier snapshot slot
+    pub excluded_supply: u64,
+    /// Claims close after this timestamp; `0` never expires
+    pub claim_deadline: i64,
+    /// Whether the unclaimed remainder has been swept
+    pub swept: bool,
+    /// Whether the distribution was voided; nothing can be claimed from it
+    pub voided: bool,
+    /// Epoch this distribution tops up, for corrective distributions
+    pub corrects_epoch: Option<u64>,
+    /// Number of `ClaimRecord`s created for this distribution
+    pub claim_count: u32,
+    /// Amount settled by range claims, which create no `ClaimRecord`
+    pub range_claimed: u64,
+}
+
+impl DistributionRecord {
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.claim_deadline != 0 && now > self.claim_deadline
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Default)]
+pub enum DistributionMode {
+    /// Claims prove a snapshot balance and are paid `entitlement(balance, amount_per_token)`
+    #[default]
+    BalanceSnapshot,
+    /// Claims prove a precomputed amount and are tracked in a `ClaimBitmap`
+    MerkleAmounts,
+}
+
+#[account]
+#[derive(Default)]
+pub struct ExcludedAccount {
+    /// The dividend pool
+    pub pool: Pubkey,
+    /// Property token account that is not circulating supply
+    pub token_account: Pubkey,
+    /// Owner of the token account, as it appears in balance snapshots
+    pub owner: Pubkey,
+    /// Wallet that claims the owner's share; `None` excludes the balance entirely
+    pub beneficiary: Option<Pubkey>,
+    /// Registration timestamp
+    pub registered_at: i64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+#[account]
+#[derive(Default)]
+pub struct StreamState {
+    /// The dividend pool
+    pub pool: Pubkey,
+    /// The property token mint whose balances earn the stream
+    pub property_mint: Pubkey,
+    /// Seconds over which each deposit is streamed
+    pub stream_period: i64,
+    /// Payout streamed per second, scaled by `AMOUNT_PER_TOKEN_SCALE`
+    pub reward_rate: u128,
+    /// When the current stream runs out
+    pub period_finish: i64,
+    /// When `reward_per_token` was last brought up to date
+    pub last_update: i64,
+    /// Payout per checkpointed token accumulated so far, scaled by `AMOUNT_PER_TOKEN_SCALE`
+    pub reward_per_token: u128,
+    /// Scaled payout streamed while no balance was checkpointed, accrued by tokens
+    /// that left a position between checkpoints, or lost to rounding; it is
+    /// streamed again with the next deposit
+    pub unallocated: u128,
+    /// Sum of the balances recorded at each position's last checkpoint
+    pub total_checkpointed: u64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl StreamState {
+    /// Bring `reward_per_token` up to `now`
+    pub fn accrue(&mut self, now: i64) -> Result<()> {
+        let until = now.min(self.period_finish);
+        if until > self.last_update {
+            let streamed = ((until - self.last_update) as u128)
+                .checked_mul(self.reward_rate)
+                .ok_or(DividendError::Overflow)?;
+            if self.total_checkpointed == 0 {
+                self.unallocated = self
+                    .unallocated
+                    .checked_add(streamed)
+                    .ok_or(DividendError::Overflow)?;
+            } else {
+                self.reward_per_token = self
+                    .reward_per_token
+                    .checked_add(streamed / self.total_checkpointed as u128)
+                    .ok_or(DividendError::Overflow)?;
+                self.unallocated = self
+                    .unallocated
+                    .checked_add(streamed % self.total_checkpointed as u128)
+                    .ok_or(DividendError::Overflow)?;
+            }
+            self.last_update = until;
+        }
+        Ok(())
+    }
+
+    /// Stream `amount` over the next `stream_period`, together with whatever is
+    /// left of the current stream
+    pub fn fund(&mut self, amount: u64, now: i64) -> Result<()> {
+        self.accrue(now)?;
+        
+        let mut total = (amount as u128)
+            .checked_mul(AMOUNT_PER_TOKEN_SCALE)
+            .and_then(|scaled| scaled.checked_add(self.unallocated))
+            .ok_or(DividendError::Overflow)?;
+        if now < self.period_finish {
+            let leftover = ((self.period_finish - now) as u128)
+                .checked_mul(self.reward_rate)
+                .ok_or(DividendError::Overflow)?;
+            total = total.checked_add(leftover).ok_or(DividendError::Overflow)?;
+        }
+        
+        let period = self.stream_period as u128;
+        self.reward_rate = total / period;
+        self.unallocated = total % period;
+        self.last_update = now;
+        self.period_finish = now
+            .checked_add(self.stream_period)
+            .ok_or(DividendError::Overflow)?;
+        Ok(())
+    }
+}
+
+#[account]
+#[derive(Default)]
+pub struct StreamPosition {
+    /// The dividend pool
+    pub pool: Pubkey,
+    /// Holder whose associated token account balance earns
+    pub owner: Pubkey,
+    /// Property tokens held at the last checkpoint
+    pub balance: u64,
+    /// `StreamState.reward_per_token` at the last checkpoint
+    pub reward_per_token_paid: u128,
+    /// Accrued and not yet claimed
+    pub accrued: u64,
+    /// Total claimed
+    pub total_claimed: u64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl StreamPosition {
+    /// Checkpoint what the position earned up to the stream's `reward_per_token`
+    /// and record `balance` as what it earns on from here. Only tokens still held
+    /// earn for the time since the last checkpoint; what tokens that left in
+    /// between accrued goes back to the stream.
+    pub fn checkpoint(&mut self, stream: &mut StreamState, balance: u64) -> Result<()> {
+        let step = stream
+            .reward_per_token
+            .checked_sub(self.reward_per_token_paid)
+            .ok_or(DividendError::Overflow)?;
+        let held = self.balance.min(balance);
+        let earned = entitlement(held, step)?;
+        self.accrued = self.accrued.checked_add(earned).ok_or(DividendError::Overflow)?;
+        let left = ((self.balance - held) as u128)
+            .checked_mul(step)
+            .ok_or(DividendError::Overflow)?;
+        stream.unallocated = stream
+            .unallocated
+            .checked_add(left)
+            .ok_or(DividendError::Overflow)?;
+        stream.total_checkpointed = stream
+            .total_checkpointed
+            .checked_sub(self.balance)
+            .and_then(|total| total.checked_add(balance))
+            .ok_or(DividendError::Overflow)?;
+        self.balance = balance;
+        self.reward_per_token_paid = stream.reward_per_token;
+        Ok(())
+    }
+}
+
+#[account]
+#[derive(Default)]
+pub struct ClaimBitmap {
+    /// Distribution record
+    pub distribution: Pubkey,
+    /// One bit per holder index, set once claimed
+    pub claimed: Vec<u8>,
+    /// PDA bump
+    pub bump: u8,
+}
+
+#[account]
+#[derive(Default)]
+pub struct ClaimRecompute {
+    /// Distribution whose `total_claimed` is being recomputed
+    pub distribution: Pubkey,
+    /// The distribution's claim count when the recompute started
+    pub claim_count: u32,
+    /// The distribution's range-claimed amount when the recompute started
+    pub range_claimed: u64,
+    /// Claim records counted so far
+    pub records_counted: u32,
+    /// Sum of the counted records' claimed amounts
+    pub amount_claimed: u64,
+    /// Highest claim record address counted; later batches continue above it
+    pub last_record: Pubkey,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl ClaimBitmap {
+    pub fn bytes_for(holder_count: u32) -> usize {
+        (holder_count as usize).div_ceil(8)
+    }
+
+    pub fn is_claimed(&self, index: u32) -> bool {
+        self.claimed[index as usize / 8] & (1 << (index % 8)) != 0
+    }
+
+    pub fn set_claimed(&mut self, index: u32) {
+        self.claimed[index as usize / 8] |= 1 << (index % 8);
+    }
+}
+
+#[account]
+#[derive(Default)]
+pub struct HolderCursor {
+    /// The dividend pool
+    pub pool: Pubkey,
+    /// Wallet whose snapshot balances are claimed
+    pub holder: Pubkey,
+    /// First epoch not yet settled by `claim_dividends_range`
+    pub next_epoch: u64,
+    /// Total claimed through `claim_dividends_range`
+    pub total_claimed: u64,
+    /// PDA bump
+    pub bump: u8,
+    /// Epoch the holder's first range claim started at
+    pub first_epoch: u64,
+}
+
+impl HolderCursor {
+    /// Whether range claims settled `epoch`
+    pub fn settles(&self, epoch: u64) -> bool {
+        epoch >= self.first_epoch && epoch < self.next_epoch
+    }
+}
+
+/// Result of the claimable-amount views
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct ClaimableAmount {
+    /// Amount a claim would pay now
+    pub claimable_now: u64,
+    /// Amount already claimed
+    pub already_claimed: u64,
+    /// Claim deadline; `0` never expires
+    pub claim_deadline: i64,
+    /// Entitlement given up, by `skip_epochs` or by letting the epoch expire
+    pub forfeited: u64,
+}
+
+/// A holder's snapshot balance and its proof for one epoch of a range claim
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct EpochClaim {
+    pub snapshot_balance: u64,
+    pub proof: Vec<[u8; 32]>,
+}
+
+#[account]
+#[derive(Default)]
+pub struct ClaimRecord {
+    /// User who claimed
+    pub user: Pubkey,
+    /// Distribution record
+    pub distribution: Pubkey,
+    /// Epoch number
+    pub epoch: u64,
+    /// Amount claimed
+    pub amount_claimed: u64,
+    /// Claim timestamp
+    pub claimed_at: i64,
+    /// Whether claimed
+    pub claimed: bool,
+    /// PDA bump
+    pub bump: u8,
+    /// Lamports of the claim spent on property tokens
+    pub reinvested_amount: u64,
+    /// Property tokens bought with the claim
+    pub tokens_reinvested: u64,
+    /// Tax withheld from `amount_claimed`
+    pub withheld_amount: u64,
+    /// Amount paid to the holder after withholding
+    pub net_amount: u64,
+}
+
+#[account]
+#[derive(Default)]
+pub struct Waterfall {
+    /// The common class's dividend pool, whose deposits the waterfall splits
+    pub pool: Pubkey,
+    /// Dividend pool paying the preferred class
+    pub preferred_pool: Pubkey,
+    /// The preferred class's token mint
+    pub preferred_mint: Pubkey,
+    /// Capital the preferred return accrues on, in the payout asset
+    pub preferred_capital: u64,
+    /// Annual preferred return, in basis points of `preferred_capital`
+    pub preferred_return_bps: u16,
+    /// Common's share of everything allocated that the catch-up tier restores
+    pub catch_up_bps: u16,
+    /// Preferred share of the residual after the catch-up, in basis points
+    pub residual_preferred_bps: u16,
+    /// Preferred return accrued and not yet allocated
+    pub accrued_preferred_return: u64,
+    /// When the preferred return last accrued
+    pub last_accrual: i64,
+    /// Total allocated to the preferred class
+    pub preferred_allocated: u64,
+    /// Total allocated to the common class
+    pub common_allocated: u64,
+    /// Preferred allocation not yet moved to the preferred pool
+    pub preferred_pending: u64,
+    /// PDA bump
+    pub bump: u8,
+    /// Common allocation returned to the pool's deposits by a void, a rollover
+    /// sweep or rounding; the next distribution pays it to common without
+    /// splitting it again
+    pub common_carried: u64,
+}
+
+impl Waterfall {
+    /// Accrue the preferred return up to `now`
+    pub fn accrue(&mut self, now: i64) -> Result<()> {
+        let elapsed = now.saturating_sub(self.last_accrual).max(0);
+        let accrued = (self.preferred_capital as u128)
+            .checked_mul(self.preferred_return_bps as u128)
+            .and_then(|value| value.checked_mul(elapsed as u128))
+            .ok_or(DividendError::Overflow)?
+            / (10_000 * SECONDS_PER_YEAR as u128);
+        self.accrued_preferred_return = u64::try_from(accrued)
+            .ok()
+            .and_then(|accrued| self.accrued_preferred_return.checked_add(accrued))
+            .ok_or(DividendError::Overflow)?;
+        self.last_accrual = now;
+        Ok(())
+    }
+
+    /// Split `available` between the classes at `now`, tier by tier, and return the
+    /// preferred class's share, which is added to `preferred_pending`
+    pub fn allocate(&mut self, available: u64, now: i64) -> Result<u64> {
+        self.accrue(now)?;
+        
+        // Preferred return
+        let preferred_return = available.min(self.accrued_preferred_return);
+        self.accrued_preferred_return -= preferred_return;
+        let preferred_allocated = self
+            .preferred_allocated
+            .checked_add(preferred_return)
+            .ok_or(DividendError::Overflow)?;
+        let mut rest = available - preferred_return;
+        
+        // Catch-up: all to common until it holds `catch_up_bps` of the total
+        let target = (preferred_allocated as u128)
+            .checked_mul(self.catch_up_bps as u128)
+            .ok_or(DividendError::Overflow)?
+            / (10_000 - self.catch_up_bps as u128);
+        let catch_up = target
+            .saturating_sub(self.common_allocated as u128)
+            .min(rest as u128) as u64;
+        rest -= catch_up;
+        
+        // Residual split
+        let preferred = preferred_return
+            .checked_add(bps_of(rest, self.residual_preferred_bps)?)
+            .ok_or(DividendError::Overflow)?;
+        let common = available - preferred;
+        
+        self.preferred_allocated = self
+            .preferred_allocated
+            .checked_add(preferred)
+            .ok_or(DividendError::Overflow)?;
+        self.common_allocated = self
+            .common_allocated
+            .checked_add(common)
+            .ok_or(DividendError::Overflow)?;
+        self.preferred_pending = self
+            .preferred_pending
+            .checked_add(preferred)
+            .ok_or(DividendError::Overflow)?;
+        Ok(preferred)
+    }
+
+    /// Split the pool's `available` deposits at `now` and return the preferred
+    /// class's share. Common's carried allocation goes back to common; only the
+    /// rest runs through the tiers.
+    pub fn split(&mut self, available: u64, now: i64) -> Result<u64> {
+        let carried = self.common_carried.min(available);
+        self.common_carried = 0;
+        self.allocate(available - carried, now)
+    }
+
+    /// Record `amount` of common's allocation returned to the pool's deposits
+    pub fn return_common(&mut self, amount: u64) -> Result<()> {
+        self.common_carried = self
+            .common_carried
+            .checked_add(amount)
+            .ok_or(DividendError::Overflow)?;
+        Ok(())
+    }
+}
+
+#[account]
+#[derive(Default)]
+pub struct PayoutPreference {
+    /// The dividend pool
+    pub pool: Pubkey,
+    /// Holder who set the preference
+    pub holder: Pubkey,
+    /// Wallet that receives the holder's claims
+    pub payout_address: Pubkey,
+    /// Wallet allowed to trigger claims for the holder
+    pub delegate: Option<Pubkey>,
+    /// Last update timestamp
+    pub updated_at: i64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+#[account]
+#[derive(Default)]
+pub struct Depositor {
+    /// The dividend pool
+    pub pool: Pubkey,
+    /// Wallet allowed to deposit
+    pub depositor: Pubkey,
+    /// Whether the depositor is currently on the allow-list
+    pub active: bool,
+    /// Total payout asset deposited by this depositor
+    pub total_deposited: u64,
+    /// Number of deposits made
+    pub deposit_count: u64,
+    /// When the depositor was last added
+    pub added_at: i64,
+    /// Last deposit timestamp
+    pub last_deposit_at: i64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+#[account]
+#[derive(Default)]
+pub struct TaxProfile {
+    /// The dividend pool
+    pub pool: Pubkey,
+    /// Holder the profile applies to
+    pub holder: Pubkey,
+    /// Share of each payout withheld, in basis points
+    pub withholding_bps: u16,
+    /// ISO 3166-1 alpha-2 code of the holder's tax jurisdiction
+    pub jurisdiction: [u8; 2],
+    /// Last update timestamp
+    pub updated_at: i64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+// ============================================================================
+// Contexts
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(property_id: String)]
+pub struct InitializePool<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    /// The property token mint
+    pub property_mint: Account<'info, Mint>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 32 + 32 + 4 + 64 + 8 + 8 + 8 + 8 + 8 + 1 + 4 + 33 + 8 + 1 + 32 + 1 + 32 + 2 + 2 + 32 + 8 + 8 + 1 + 64,
+        seeds = [b"dividend_pool", property_mint.key().as_ref(), payout_mint_seed(&payout_mint)],
+        bump
+    )]
+    pub dividend_pool: Account<'info, DividendPool>,
+    
+    /// CHECK: PDA vault for holding SOL dividends
+    #[account(
+        mut,
+        seeds = [b"dividend_vault", dividend_pool.key().as_ref()],
+        bump
+    )]
+    pub dividend_vault: AccountInfo<'info>,
+    
+    /// CHECK: PDA collecting withheld SOL; funded rent-exempt for SOL pools
+    #[account(
+        mut,
+        seeds = [b"tax_vault", dividend_pool.key().as_ref()],
+        bump
+    )]
+    pub tax_vault: AccountInfo<'info>,
+    
+    /// CHECK: PDA accruing the reserve; funded rent-exempt for SOL pools
+    #[account(
+        mut,
+        seeds = [b"reserve_vault", dividend_pool.key().as_ref()],
+        bump
+    )]
+    pub reserve_vault: AccountInfo<'info>,
+    
+    /// Token the pool pays out; omit for a SOL pool
+    pub payout_mint: Option<Account<'info, Mint>>,
+    
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"dividend_token_vault", dividend_pool.key().as_ref()],
+        bump,
+        token::mint = payout_mint,
+        token::authority = dividend_vault
+    )]
+    pub token_vault: Option<Account<'info, TokenAccount>>,
+    
+    /// CHECK: Optional crowdfunding campaign proving the authority issued the mint;
+    /// owner and layout are verified in the handler
+    pub campaign: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: Optional crowdfunding platform config proving the authority is the
+    /// platform admin; address and layout are verified in the handler
+    pub platform_config: Option<UncheckedAccount<'info>>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct DepositDividend<'info> {
+    #[account(
+        mut,
+        constraint = depositor.key() == dividend_pool.authority
+            || depositor_record.as_ref().is_some_and(|record| record.active) @ DividendError::Unauthorized
+    )]
+    pub depositor: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"dividend_pool", dividend_pool.property_mint.as_ref(), dividend_pool.payout_mint_seed()],
+        bump = dividend_pool.bump
+    )]
+    pub dividend_pool: Account<'info, DividendPool>,
+    
+    /// CHECK: PDA vault for holding SOL dividends
+    #[account(
+        mut,
+        seeds = [b"dividend_vault", dividend_pool.key().as_ref()],
+        bump
+    )]
+    pub dividend_vault: AccountInfo<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"dividend_token_vault", dividend_pool.key().as_ref()],
+        bump
+    )]
+    pub token_vault: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        constraint = Some(depositor_token_account.mint) == dividend_pool.payout_mint @ DividendError::InvalidMint
+    )]
+    pub depositor_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"depositor", dividend_pool.key().as_ref(), depositor.key().as_ref()],
+        bump = depositor_record.bump
+    )]
+    pub depositor_record: Option<Account<'info, Depositor>>,
+    
+    #[account(
+        mut,
+        seeds = [b"stream", dividend_pool.key().as_ref()],
+        bump = stream_state.bump
+    )]
+    pub stream_state: Option<Account<'info, StreamState>>,
+    
+    pub token_program: Option<Program<'info, Token>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct StartDistribution<'info> {
+    #[account(
+        mut,
+        constraint = authority.key() == dividend_pool.authority @ DividendError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"dividend_pool", dividend_pool.property_mint.as_ref(), dividend_pool.payout_mint_seed()],
+        bump = dividend_pool.bump
+    )]
+    pub dividend_pool: Account<'info, DividendPool>,
+    
+    #[account(address = dividend_pool.property_mint @ DividendError::InvalidMint)]
+    pub property_mint: Account<'info, Mint>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 8 + 8 + 8 + 16 + 8 + 8 + 1 + 8 + 32 + 1 + 4 + 8 + 8 + 1 + 1 + 9 + 4 + 8 + 32,
+        seeds = [b"distribution", dividend_pool.key().as_ref(), &dividend_pool.current_epoch.to_le_bytes()],
+        bump
+    )]
+    pub distribution_record: Account<'info, DistributionRecord>,
+    
+    /// CHECK: PDA vault for holding SOL dividends
+    #[account(
+        mut,
+        seeds = [b"dividend_vault", dividend_pool.key().as_ref()],
+        bump
+    )]
+    pub dividend_vault: AccountInfo<'info>,
+    
+    /// CHECK: Receives the management fee of SOL pools
+    #[account(mut, address = dividend_pool.manager @ DividendError::InvalidManager)]
+    pub manager: UncheckedAccount<'info>,
+    
+    /// CHECK: PDA accruing the reserve
+    #[account(
+        mut,
+        seeds = [b"reserve_vault", dividend_pool.key().as_ref()],
+        bump
+    )]
+    pub reserve_vault: AccountInfo<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"dividend_token_vault", dividend_pool.key().as_ref()],
+        bump
+    )]
+    pub token_vault: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        constraint = Some(manager_token_account.mint) == dividend_pool.payout_mint @ DividendError::InvalidMint,
+        constraint = manager_token_account.owner == dividend_pool.manager @ DividendError::InvalidTokenOwner
+    )]
+    pub manager_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"reserve_token_vault", dividend_pool.key().as_ref()],
+        bump
+    )]
+    pub reserve_token_vault: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"waterfall", dividend_pool.key().as_ref()],
+        bump = waterfall.bump
+    )]
+    pub waterfall: Option<Account<'info, Waterfall>>,
+    
+    pub token_program: Option<Program<'info, Token>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CrankDistribution<'info> {
+    #[account(mut)]
+    pub cranker: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"dividend_pool", dividend_pool.property_mint.as_ref(), dividend_pool.payout_mint_seed()],
+        bump = dividend_pool.bump
+    )]
+    pub dividend_pool: Account<'info, DividendPool>,
+    
+    #[account(address = dividend_pool.property_mint @ DividendError::InvalidMint)]
+    pub property_mint: Account<'info, Mint>,
+    
+    #[account(
+        init,
+        payer = cranker,
+        space = 8 + 32 + 8 + 8 + 8 + 16 + 8 + 8 + 1 + 8 + 32 + 1 + 4 + 8 + 8 + 1 + 1 + 9 + 4 + 8 + 32,
+        seeds = [b"distribution", dividend_pool.key().as_ref(), &dividend_pool.current_epoch.to_le_bytes()],
+        bump
+    )]
+    pub distribution_record: Account<'info, DistributionRecord>,
+    
+    /// CHECK: PDA vault for holding SOL dividends
+    #[account(
+        mut,
+        seeds = [b"dividend_vault", dividend_pool.key().as_ref()],
+        bump
+    )]
+    pub dividend_vault: AccountInfo<'info>,
+    
+    /// CHECK: Receives the management fee of SOL pools
+    #[account(mut, address = dividend_pool.manager @ DividendError::InvalidManager)]
+    pub manager: UncheckedAccount<'info>,
+    
+    /// CHECK: PDA accruing the reserve
+    #[account(
+        mut,
+        seeds = [b"reserve_vault", dividend_pool.key().as_ref()],
+        bump
+    )]
+    pub reserve_vault: AccountInfo<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"dividend_token_vault", dividend_pool.key().as_ref()],
+        bump
+    )]
+    pub token_vault: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        constraint = Some(manager_token_account.mint) == dividend_pool.payout_mint @ DividendError::InvalidMint,
+        constraint = manager_token_account.owner == dividend_pool.manager @ DividendError::InvalidTokenOwner
+    )]
+    pub manager_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"reserve_token_vault", dividend_pool.key().as_ref()],
+        bump
+    )]
+    pub reserve_token_vault: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"waterfall", dividend_pool.key().as_ref()],
+        bump = waterfall.bump
+    )]
+    pub waterfall: Option<Account<'info, Waterfall>>,
+    
+    pub token_program: Option<Program<'info, Token>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(epoch: u64)]
+pub struct PostSnapshotRoot<'info> {
+    #[account(
+        constraint = authority.key() == dividend_pool.authority @ DividendError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"dividend_pool", dividend_pool.property_mint.as_ref(), dividend_pool.payout_mint_seed()],
+        bump = dividend_pool.bump
+    )]
+    pub dividend_pool: Account<'info, DividendPool>,
+    
+    #[account(
+        mut,
+        seeds = [b"distribution", dividend_pool.key().as_ref(), &epoch.to_le_bytes()],
+        bump = distribution_record.bump
+    )]
+    pub distribution_record: Account<'info, DistributionRecord>,
+}
+
+#[derive(Accounts)]
+#[instruction(merkle_root: [u8; 32], total_amount: u64, holder_count: u32)]
+pub struct StartMerkleDistribution<'info> {
+    #[account(
+        mut,
+        constraint = authority.key() == dividend_pool.authority @ DividendError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"dividend_pool", dividend_pool.property_mint.as_ref(), dividend_pool.payout_mint_seed()],
+        bump = dividend_pool.bump
+    )]
+    pub dividend_pool: Account<'info, DividendPool>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 8 + 8 + 8 + 16 + 8 + 8 + 1 + 8 + 32 + 1 + 4 + 8 + 8 + 1 + 1 + 9 + 4 + 8 + 32,
+        seeds = [b"distribution", dividend_pool.key().as_ref(), &dividend_pool.current_epoch.to_le_bytes()],
+        bump
+    )]
+    pub distribution_record: Account<'info, DistributionRecord>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 4 + ClaimBitmap::bytes_for(holder_count) + 1,
+        seeds = [b"claim_bitmap", distribution_record.key().as_ref()],
+        bump
+    )]
+    pub claim_bitmap: Account<'info, ClaimBitmap>,
+    
+    /// CHECK: PDA vault for holding SOL dividends
+    #[account(
+        mut,
+        seeds = [b"dividend_vault", dividend_pool.key().as_ref()],
+        bump
+    )]
+    pub dividend_vault: AccountInfo<'info>,
+    
+    /// CHECK: Receives the management fee of SOL pools
+    #[account(mut, address = dividend_pool.manager @ DividendError::InvalidManager)]
+    pub manager: UncheckedAccount<'info>,
+    
+    /// CHECK: PDA accruing the reserve
+    #[account(
+        mut,
+        seeds = [b"reserve_vault", dividend_pool.key().as_ref()],
+        bump
+    )]
+    pub reserve_vault: AccountInfo<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"dividend_token_vault", dividend_pool.key().as_ref()],
+        bump
+    )]
+    pub token_vault: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        constraint = Some(manager_token_account.mint) == dividend_pool.payout_mint @ DividendError::InvalidMint,
+        constraint = manager_token_account.owner == dividend_pool.manager @ DividendError::InvalidTokenOwner
+    )]
+    pub manager_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"reserve_token_vault", dividend_pool.key().as_ref()],
+        bump
+    )]
+    pub reserve_token_vault: Option<Account<'info, TokenAccount>>,
+    
+    pub token_program: Option<Program<'info, Token>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(epoch: u64)]
+pub struct ClaimMerkleDividend<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(
+        seeds = [b"dividend_pool", dividend_pool.property_mint.as_ref(), dividend_pool.payout_mint_seed()],
+        bump = dividend_pool.bump
+    )]
+    pub dividend_pool: Account<'info, DividendPool>,
+    
+    #[account(
+        mut,
+        seeds = [b"distribution", dividend_pool.key().as_ref(), &epoch.to_le_bytes()],
+        bump = distribution_record.bump
+    )]
+    pub distribution_record: Account<'info, DistributionRecord>,
+    
+    #[account(
+        mut,
+        seeds = [b"claim_bitmap", distribution_record.key().as_ref()],
+        bump = claim_bitmap.bump
+    )]
+    pub claim_bitmap: Account<'info, ClaimBitmap>,
+    
+    /// CHECK: PDA vault for holding SOL dividends
+    #[account(
+        mut,
+        seeds = [b"dividend_vault", dividend_pool.key().as_ref()],
+        bump
+    )]
+    pub dividend_vault: AccountInfo<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"dividend_token_vault", dividend_pool.key().as_ref()],
+        bump
+    )]
+    pub token_vault: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        constraint = Some(recipient_token_account.mint) == dividend_pool.payout_mint @ DividendError::InvalidMint,
+        constraint = recipient_token_account.owner == user.key() @ DividendError::InvalidTokenOwner
+    )]
+    pub recipient_token_account: Option<Account<'info, TokenAccount>>,
+    
+    /// CHECK: Holder's tax profile, which may not exist; read in the handler
+    #[account(
+        seeds = [b"tax_profile", dividend_pool.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub tax_profile: UncheckedAccount<'info>,
+    
+    /// CHECK: PDA receiving withheld SOL
+    #[account(
+        mut,
+        seeds = [b"tax_vault", dividend_pool.key().as_ref()],
+        bump
+    )]
+    pub tax_vault: AccountInfo<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"tax_token_vault", dividend_pool.key().as_ref()],
+        bump
+    )]
+    pub tax_token_vault: Option<Account<'info, TokenAccount>>,
+    
+    pub token_program: Option<Program<'info, Token>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(epoch: u64)]
+pub struct ClaimDividend<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    /// CHECK: Wallet whose snapshot balance is claimed; must be `user` unless
+    /// `redirect` names `user` as its beneficiary
+    pub holder: UncheckedAccount<'info>,
+    
+    #[account(
+        constraint = redirect.pool == dividend_pool.key() @ DividendError::Unauthorized
+    )]
+    pub redirect: Option<Account<'info, ExcludedAccount>>,
+    
+    #[account(
+        seeds = [b"payout", dividend_pool.key().as_ref(), holder.key().as_ref()],
+        bump = payout_preference.bump
+    )]
+    pub payout_preference: Option<Account<'info, PayoutPreference>>,
+    
+    /// CHECK: SOL destination when the holder's preference names a payout address;
+    /// checked against it in the handler
+    #[account(mut)]
+    pub payout_address: Option<UncheckedAccount<'info>>,
+    
+    #[account(
+        seeds = [b"dividend_pool", dividend_pool.property_mint.as_ref(), dividend_pool.payout_mint_seed()],
+        bump = dividend_pool.bump
+    )]
+    pub dividend_pool: Account<'info, DividendPool>,
+    
+    #[account(
+        mut,
+        seeds = [b"distribution", dividend_pool.key().as_ref(), &epoch.to_le_bytes()],
+        bump = distribution_record.bump
+    )]
+    pub distribution_record: Account<'info, DistributionRecord>,
+    
+    /// CHECK: PDA vault for holding SOL dividends
+    #[account(
+        mut,
+        seeds = [b"dividend_vault", dividend_pool.key().as_ref()],
+        bump
+    )]
+    pub dividend_vault: AccountInfo<'info>,
+    
+    /// CHECK: Holder's claim record, created in the handler unless the claim
+    /// settles on the holder's cursor
+    #[account(
+        mut,
+        seeds = [b"claim", distribution_record.key().as_ref(), holder.key().as_ref()],
+        bump
+    )]
+    pub claim_record: UncheckedAccount<'info>,
+    
+    /// CHECK: Holder's range-claim cursor, which may not exist yet; read and
+    /// moved in the handler
+    #[account(
+        mut,
+        seeds = [b"cursor", dividend_pool.key().as_ref(), holder.key().as_ref()],
+        bump
+    )]
+    pub holder_cursor: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"dividend_token_vault", dividend_pool.key().as_ref()],
+        bump
+    )]
+    pub token_vault: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        constraint = Some(recipient_token_account.mint) == dividend_pool.payout_mint @ DividendError::InvalidMint,
+        constraint = recipient_token_account.owner
+            == payout_recipient(user.key(), payout_preference.as_ref()) @ DividendError::InvalidTokenOwner
+    )]
+    pub recipient_token_account: Option<Account<'info, TokenAccount>>,
+    
+    /// CHECK: Holder's tax profile, which may not exist; read in the handler
+    #[account(
+        seeds = [b"tax_profile", dividend_pool.key().as_ref(), holder.key().as_ref()],
+        bump
+    )]
+    pub tax_profile: UncheckedAccount<'info>,
+    
+    /// CHECK: PDA receiving withheld SOL
+    #[account(
+        mut,
+        seeds = [b"tax_vault", dividend_pool.key().as_ref()],
+        bump
+    )]
+    pub tax_vault: AccountInfo<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"tax_token_vault", dividend_pool.key().as_ref()],
+        bump
+    )]
+    pub tax_token_vault: Option<Account<'info, TokenAccount>>,
+    
+    pub token_program: Option<Program<'info, Token>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(epoch: u64)]
+pub struct ReinvestDividend<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(
+        seeds = [b"dividend_pool", dividend_pool.property_mint.as_ref(), dividend_pool.payout_mint_seed()],
+        bump = dividend_pool.bump
+    )]
+    pub dividend_pool: Box<Account<'info, DividendPool>>,
+    
+    #[account(
+        mut,
+        seeds = [b"distribution", dividend_pool.key().as_ref(), &epoch.to_le_bytes()],
+        bump = distribution_record.bump
+    )]
+    pub distribution_record: Box<Account<'info, DistributionRecord>>,
+    
+    /// CHECK: PDA vault for holding SOL dividends; buys the tokens
+    #[account(
+        mut,
+        seeds = [b"dividend_vault", dividend_pool.key().as_ref()],
+        bump
+    )]
+    pub dividend_vault: AccountInfo<'info>,
+    
+    /// CHECK: Holder's claim record, created in the handler unless the claim
+    /// settles on the holder's cursor
+    #[account(
+        mut,
+        seeds = [b"claim", distribution_record.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub claim_record: UncheckedAccount<'info>,
+    
+    /// CHECK: Holder's range-claim cursor, which may not exist yet; read and
+    /// moved in the handler
+    #[account(
+        mut,
+        seeds = [b"cursor", dividend_pool.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub holder_cursor: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        constraint = holder_token_account.mint == dividend_pool.property_mint @ DividendError::InvalidMint,
+        constraint = holder_token_account.owner == user.key() @ DividendError::InvalidTokenOwner
+    )]
+    pub holder_token_account: Box<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        associated_token::mint = property_mint,
+        associated_token::authority = dividend_vault
+    )]
+    pub vault_token_account: Box<Account<'info, TokenAccount>>,
+    
+    #[account(address = dividend_pool.property_mint @ DividendError::InvalidMint)]
+    pub property_mint: Box<Account<'info, Mint>>,
+    
+    #[account(
+        mut,
+        constraint = listing.token_mint == dividend_pool.property_mint @ DividendError::InvalidMint
+    )]
+    pub listing: Box<Account<'info, marketplace::Listing>>,
+    
+    /// CHECK: Listing seller; checked by the marketplace
+    #[account(mut)]
+    pub seller: UncheckedAccount<'info>,
+    
+    /// CHECK: Marketplace fee recipient. The marketplace stores no fee wallet and
+    /// takes any account here, as it does for direct purchases; the fee comes out
+    /// of the signing holder's own dividend
+    #[account(mut)]
+    pub platform_wallet: UncheckedAccount<'info>,
+    
+    /// CHECK: Marketplace state; checked by the marketplace
+    #[account(mut)]
+    pub marketplace: UncheckedAccount<'info>,
+    
+    /// CHECK: Listing escrow; checked by the marketplace
+    #[account(mut)]
+    pub escrow_token_account: UncheckedAccount<'info>,
+    
+    /// CHECK: Holder's tax profile, which may not exist; read in the handler
+    #[account(
+        seeds = [b"tax_profile", dividend_pool.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub tax_profile: UncheckedAccount<'info>,
+    
+    /// CHECK: PDA receiving withheld SOL
+    #[account(
+        mut,
+        seeds = [b"tax_vault", dividend_pool.key().as_ref()],
+        bump
+    )]
+    pub tax_vault: AccountInfo<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"tax_token_vault", dividend_pool.key().as_ref()],
+        bump
+    )]
+    pub tax_token_vault: Option<Account<'info, TokenAccount>>,
+    
+    pub marketplace_program: Program<'info, marketplace::program::Marketplace>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimDividendsRange<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    /// CHECK: Wallet whose snapshot balances are claimed; must be `user` unless
+    /// `redirect` names `user` as its beneficiary
+    pub holder: UncheckedAccount<'info>,
+    
+    #[account(
+        constraint = redirect.pool == dividend_pool.key() @ DividendError::Unauthorized
+    )]
+    pub redirect: Option<Account<'info, ExcludedAccount>>,
+    
+    #[account(
+        seeds = [b"payout", dividend_pool.key().as_ref(), holder.key().as_ref()],
+        bump = payout_preference.bump
+    )]
+    pub payout_preference: Option<Account<'info, PayoutPreference>>,
+    
+    /// CHECK: SOL destination when the holder's preference names a payout address;
+    /// checked against it in the handler
+    #[account(mut)]
+    pub payout_address: Option<UncheckedAccount<'info>>,
+    
+    #[account(
+        seeds = [b"dividend_pool", dividend_pool.property_mint.as_ref(), dividend_pool.payout_mint_seed()],
+        bump = dividend_pool.bump
+    )]
+    pub dividend_pool: Account<'info, DividendPool>,
+    
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + 32 + 32 + 8 + 8 + 1 + 8 + 24,
+        seeds = [b"cursor", dividend_pool.key().as_ref(), holder.key().as_ref()],
+        bump
+    )]
+    pub holder_cursor: Account<'info, HolderCursor>,
+    
+    /// CHECK: PDA vault for holding SOL dividends
+    #[account(
+        mut,
+        seeds = [b"dividend_vault", dividend_pool.key().as_ref()],
+        bump
+    )]
+    pub dividend_vault: AccountInfo<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"dividend_token_vault", dividend_pool.key().as_ref()],
+        bump
+    )]
+    pub token_vault: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        constraint = Some(recipient_token_account.mint) == dividend_pool.payout_mint @ DividendError::InvalidMint,
+        constraint = recipient_token_account.owner
+            == payout_recipient(user.key(), payout_preference.as_ref()) @ DividendError::InvalidTokenOwner
+    )]
+    pub recipient_token_account: Option<Account<'info, TokenAccount>>,
+    
+    /// CHECK: Holder's tax profile, which may not exist; read in the handler
+    #[account(
+        seeds = [b"tax_profile", dividend_pool.key().as_ref(), holder.key().as_ref()],
+        bump
+    )]
+    pub tax_profile: UncheckedAccount<'info>,
+    
+    /// CHECK: PDA receiving withheld SOL
+    #[account(
+        mut,
+        seeds = [b"tax_vault", dividend_pool.key().as_ref()],
+        bump
+    )]
+    pub tax_vault: AccountInfo<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"tax_token_vault", dividend_pool.key().as_ref()],
+        bump
+    )]
+    pub tax_token_vault: Option<Account<'info, TokenAccount>>,
+    
+    pub token_program: Option<Program<'info, Token>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(epoch: u64)]
+pub struct PushDividends<'info> {
+    /// Pays rent for the claim records created
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+    
+    #[account(
+        seeds = [b"dividend_pool", dividend_pool.property_mint.as_ref(), dividend_pool.payout_mint_seed()],
+        bump = dividend_pool.bump
+    )]
+    pub dividend_pool: Account<'info, DividendPool>,
+    
+    #[account(
+        mut,
+        seeds = [b"distribution", dividend_pool.key().as_ref(), &epoch.to_le_bytes()],
+        bump = distribution_record.bump
+    )]
+    pub distribution_record: Account<'info, DistributionRecord>,
+    
+    /// CHECK: PDA vault for holding SOL dividends
+    #[account(
+        mut,
+        seeds = [b"dividend_vault", dividend_pool.key().as_ref()],
+        bump
+    )]
+    pub dividend_vault: AccountInfo<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"dividend_token_vault", dividend_pool.key().as_ref()],
+        bump
+    )]
+    pub token_vault: Option<Account<'info, TokenAccount>>,
+    
+    /// CHECK: PDA receiving withheld SOL
+    #[account(
+        mut,
+        seeds = [b"tax_vault", dividend_pool.key().as_ref()],
+        bump
+    )]
+    pub tax_vault: AccountInfo<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"tax_token_vault", dividend_pool.key().as_ref()],
+        bump
+    )]
+    pub tax_token_vault: Option<Account<'info, TokenAccount>>,
+    
+    pub token_program: Option<Program<'info, Token>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct EnableStreaming<'info> {
+    #[account(
+        mut,
+        constraint = authority.key() == dividend_pool.authority @ DividendError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"dividend_pool", dividend_pool.property_mint.as_ref(), dividend_pool.payout_mint_seed()],
+        bump = dividend_pool.bump
+    )]
+    pub dividend_pool: Account<'info, DividendPool>,
+    
+    #[account(address = dividend_pool.property_mint @ DividendError::InvalidMint)]
+    pub property_mint: Account<'info, Mint>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 32 + 8 + 16 + 8 + 8 + 16 + 16 + 8 + 1 + 32,
+        seeds = [b"stream", dividend_pool.key().as_ref()],
+        bump
+    )]
+    pub stream_state: Account<'info, StreamState>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CheckpointHolder<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    /// CHECK: Holder whose position is checkpointed
+    pub owner: UncheckedAccount<'info>,
+    
+    #[account(
+        seeds = [b"dividend_pool", dividend_pool.property_mint.as_ref(), dividend_pool.payout_mint_seed()],
+        bump = dividend_pool.bump
+    )]
+    pub dividend_pool: Account<'info, DividendPool>,
+    
+    #[account(
+        mut,
+        seeds = [b"stream", dividend_pool.key().as_ref()],
+        bump = stream_state.bump
+    )]
+    pub stream_state: Account<'info, StreamState>,
+    
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + 32 + 32 + 8 + 16 + 8 + 8 + 1 + 32,
+        seeds = [b"stream_position", dividend_pool.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub stream_position: Account<'info, StreamPosition>,
+    
+    /// CHECK: Holder's associated token account for the property mint, which may
+    /// not exist; read in the handler
+    #[account(
+        address = get_associated_token_address(&owner.key(), &dividend_pool.property_mint)
+            @ DividendError::InvalidTokenOwner
+    )]
+    pub holder_token_account: UncheckedAccount<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimStreamed<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    #[account(
+        seeds = [b"dividend_pool", dividend_pool.property_mint.as_ref(), dividend_pool.payout_mint_seed()],
+        bump = dividend_pool.bump
+    )]
+    pub dividend_pool: Account<'info, DividendPool>,
+    
+    #[account(
+        mut,
+        seeds = [b"stream", dividend_pool.key().as_ref()],
+        bump = stream_state.bump
+    )]
+    pub stream_state: Account<'info, StreamState>,
+    
+    #[account(
+        mut,
+        seeds = [b"stream_position", dividend_pool.key().as_ref(), owner.key().as_ref()],
+        bump = stream_position.bump
+    )]
+    pub stream_position: Account<'info, StreamPosition>,
+    
+    /// CHECK: Holder's associated token account for the property mint, which may
+    /// not exist; read in the handler
+    #[account(
+        address = get_associated_token_address(&owner.key(), &dividend_pool.property_mint)
+            @ DividendError::InvalidTokenOwner
+    )]
+    pub holder_token_account: UncheckedAccount<'info>,
+    
+    /// CHECK: PDA vault for holding SOL dividends
+    #[account(
+        mut,
+        seeds = [b"dividend_vault", dividend_pool.key().as_ref()],
+        bump
+    )]
+    pub dividend_vault: AccountInfo<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"dividend_token_vault", dividend_pool.key().as_ref()],
+        bump
+    )]
+    pub token_vault: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        constraint = Some(recipient_token_account.mint) == dividend_pool.payout_mint @ DividendError::InvalidMint,
+        constraint = recipient_token_account.owner == owner.key() @ DividendError::InvalidTokenOwner
+    )]
+    pub recipient_token_account: Option<Account<'info, TokenAccount>>,
+    
+    /// CHECK: Holder's tax profile, which may not exist; read in the handler
+    #[account(
+        seeds = [b"tax_profile", dividend_pool.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub tax_profile: UncheckedAccount<'info>,
+    
+    /// CHECK: PDA receiving withheld SOL
+    #[account(
+        mut,
+        seeds = [b"tax_vault", dividend_pool.key().as_ref()],
+        bump
+    )]
+    pub tax_vault: AccountInfo<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"tax_token_vault", dividend_pool.key().as_ref()],
+        bump
+    )]
+    pub tax_token_vault: Option<Account<'info, TokenAccount>>,
+    
+    pub token_program: Option<Program<'info, Token>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(epoch: u64)]
+pub struct CorrectDistribution<'info> {
+    #[account(
+        constraint = authority.key() == dividend_pool.authority @ DividendError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"dividend_pool", dividend_pool.property_mint.as_ref(), dividend_pool.payout_mint_seed()],
+        bump = dividend_pool.bump
+    )]
+    pub dividend_pool: Account<'info, DividendPool>,
+    
+    #[account(
+        mut,
+        seeds = [b"distribution", dividend_pool.key().as_ref(), &epoch.to_le_bytes()],
+        bump = distribution_record.bump
+    )]
+    pub distribution_record: Account<'info, DistributionRecord>,
+    
+    #[account(
+        mut,
+        seeds = [b"waterfall", dividend_pool.key().as_ref()],
+        bump = waterfall.bump
+    )]
+    pub waterfall: Option<Account<'info, Waterfall>>,
+}
+
+#[derive(Accounts)]
+#[instruction(epoch: u64)]
+pub struct RecomputeTotalClaimed<'info> {
+    #[account(
+        mut,
+        constraint = authority.key() == dividend_pool.authority @ DividendError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"dividend_pool", dividend_pool.property_mint.as_ref(), dividend_pool.payout_mint_seed()],
+        bump = dividend_pool.bump
+    )]
+    pub dividend_pool: Account<'info, DividendPool>,
+    
+    #[account(
+        mut,
+        seeds = [b"distribution", dividend_pool.key().as_ref(), &epoch.to_le_bytes()],
+        bump = distribution_record.bump
+    )]
+    pub distribution_record: Account<'info, DistributionRecord>,
+    
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + 32 + 4 + 8 + 4 + 8 + 32 + 1,
+        seeds = [b"recompute", distribution_record.key().as_ref()],
+        bump
+    )]
+    pub recompute: Account<'info, ClaimRecompute>,
+    
+    pub system_program: Program<'info, System>,
 }
 
-#[account]
-#[derive(Default)]
-pub struct DistributionRecord {
-    /// The dividend pool
-    pub pool: Pubkey,
-    /// Distribution epoch number
-    pub epoch: u64,
-    /// Total SOL distributed this epoch
-    pub total_amount: u64,
-    /// Total token supply at distribution time
-    pub total_token_supply: u64,
-    /// SOL amount per token
-    pub amount_per_token: u64,
-    /// Timestamp of distribution
-    pub distributed_at: i64,
-    /// Total amount claimed so far
-    pub total_claimed: u64,
-    /// PDA bump
-    pub bump: u8,
+#[derive(Accounts)]
+#[instruction(corrects_epoch: u64)]
+pub struct StartCorrectiveDistribution<'info> {
+    #[account(
+        mut,
+        constraint = authority.key() == dividend_pool.authority @ DividendError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"dividend_pool", dividend_pool.property_mint.as_ref(), dividend_pool.payout_mint_seed()],
+        bump = dividend_pool.bump
+    )]
+    pub dividend_pool: Account<'info, DividendPool>,
+    
+    #[account(
+        seeds = [b"distribution", dividend_pool.key().as_ref(), &corrects_epoch.to_le_bytes()],
+        bump = corrected_distribution.bump
+    )]
+    pub corrected_distribution: Account<'info, DistributionRecord>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 8 + 8 + 8 + 16 + 8 + 8 + 1 + 8 + 32 + 1 + 4 + 8 + 8 + 1 + 1 + 9 + 4 + 8 + 32,
+        seeds = [b"distribution", dividend_pool.key().as_ref(), &dividend_pool.current_epoch.to_le_bytes()],
+        bump
+    )]
+    pub distribution_record: Account<'info, DistributionRecord>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(epoch: u64)]
+pub struct GetClaimableAmount<'info> {
+    /// CHECK: Wallet whose snapshot balance is queried
+    pub holder: UncheckedAccount<'info>,
+    
+    #[account(
+        seeds = [b"dividend_pool", dividend_pool.property_mint.as_ref(), dividend_pool.payout_mint_seed()],
+        bump = dividend_pool.bump
+    )]
+    pub dividend_pool: Account<'info, DividendPool>,
+    
+    #[account(
+        seeds = [b"distribution", dividend_pool.key().as_ref(), &epoch.to_le_bytes()],
+        bump = distribution_record.bump
+    )]
+    pub distribution_record: Account<'info, DistributionRecord>,
+    
+    /// CHECK: Holder's claim record for the epoch, which may not exist
+    #[account(
+        seeds = [b"claim", distribution_record.key().as_ref(), holder.key().as_ref()],
+        bump
+    )]
+    pub claim_record: UncheckedAccount<'info>,
+    
+    /// CHECK: Holder's range-claim cursor, which may not exist
+    #[account(
+        seeds = [b"cursor", dividend_pool.key().as_ref(), holder.key().as_ref()],
+        bump
+    )]
+    pub holder_cursor: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct GetTotalClaimable<'info> {
+    /// CHECK: Wallet whose snapshot balances are queried
+    pub holder: UncheckedAccount<'info>,
+    
+    #[account(
+        seeds = [b"dividend_pool", dividend_pool.property_mint.as_ref(), dividend_pool.payout_mint_seed()],
+        bump = dividend_pool.bump
+    )]
+    pub dividend_pool: Account<'info, DividendPool>,
+    
+    /// CHECK: Holder's range-claim cursor, which may not exist
+    #[account(
+        seeds = [b"cursor", dividend_pool.key().as_ref(), holder.key().as_ref()],
+        bump
+    )]
+    pub holder_cursor: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetUnclaimedPolicy<'info> {
+    #[account(
+        constraint = authority.key() == dividend_pool.authority @ DividendError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"dividend_pool", dividend_pool.property_mint.as_ref(), dividend_pool.payout_mint_seed()],
+        bump = dividend_pool.bump
+    )]
+    pub dividend_pool: Account<'info, DividendPool>,
+}
+
+#[derive(Accounts)]
+#[instruction(epoch: u64)]
+pub struct SweepUnclaimed<'info> {
+    pub caller: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"dividend_pool", dividend_pool.property_mint.as_ref(), dividend_pool.payout_mint_seed()],
+        bump = dividend_pool.bump
+    )]
+    pub dividend_pool: Account<'info, DividendPool>,
+    
+    #[account(
+        mut,
+        seeds = [b"distribution", dividend_pool.key().as_ref(), &epoch.to_le_bytes()],
+        bump = distribution_record.bump
+    )]
+    pub distribution_record: Account<'info, DistributionRecord>,
+    
+    /// CHECK: PDA vault for holding SOL dividends
+    #[account(
+        mut,
+        seeds = [b"dividend_vault", dividend_pool.key().as_ref()],
+        bump
+    )]
+    pub dividend_vault: AccountInfo<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"dividend_token_vault", dividend_pool.key().as_ref()],
+        bump
+    )]
+    pub token_vault: Option<Account<'info, TokenAccount>>,
+    
+    /// CHECK: Pool treasury; required under the treasury policy, receives SOL payouts
+    #[account(
+        mut,
+        address = dividend_pool.treasury @ DividendError::InvalidTreasury
+    )]
+    pub treasury: Option<UncheckedAccount<'info>>,
+    
+    #[account(
+        mut,
+        constraint = Some(treasury_token_account.mint) == dividend_pool.payout_mint @ DividendError::InvalidMint,
+        constraint = treasury_token_account.owner == dividend_pool.treasury @ DividendError::InvalidTreasury
+    )]
+    pub treasury_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"waterfall", dividend_pool.key().as_ref()],
+        bump = waterfall.bump
+    )]
+    pub waterfall: Option<Account<'info, Waterfall>>,
+    
+    pub token_program: Option<Program<'info, Token>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetPayoutPreference<'info> {
+    #[account(mut)]
+    pub holder: Signer<'info>,
+    
+    #[account(
+        seeds = [b"dividend_pool", dividend_pool.property_mint.as_ref(), dividend_pool.payout_mint_seed()],
+        bump = dividend_pool.bump
+    )]
+    pub dividend_pool: Account<'info, DividendPool>,
+    
+    #[account(
+        init_if_needed,
+        payer = holder,
+        space = 8 + 32 + 32 + 32 + 33 + 8 + 1 + 32,
+        seeds = [b"payout", dividend_pool.key().as_ref(), holder.key().as_ref()],
+        bump
+    )]
+    pub payout_preference: Account<'info, PayoutPreference>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClearPayoutPreference<'info> {
+    #[account(mut)]
+    pub holder: Signer<'info>,
+    
+    #[account(
+        seeds = [b"dividend_pool", dividend_pool.property_mint.as_ref(), dividend_pool.payout_mint_seed()],
+        bump = dividend_pool.bump
+    )]
+    pub dividend_pool: Account<'info, DividendPool>,
+    
+    #[account(
+        mut,
+        close = holder,
+        seeds = [b"payout", dividend_pool.key().as_ref(), holder.key().as_ref()],
+        bump = payout_preference.bump
+    )]
+    pub payout_preference: Account<'info, PayoutPreference>,
+}
+
+#[derive(Accounts)]
+pub struct SetComplianceAuthority<'info> {
+    #[account(
+        constraint = authority.key() == dividend_pool.authority @ DividendError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"dividend_pool", dividend_pool.property_mint.as_ref(), dividend_pool.payout_mint_seed()],
+        bump = dividend_pool.bump
+    )]
+    pub dividend_pool: Account<'info, DividendPool>,
+}
+
+#[derive(Accounts)]
+#[instruction(holder: Pubkey)]
+pub struct SetTaxProfile<'info> {
+    #[account(
+        mut,
+        constraint = compliance_authority.key() == dividend_pool.compliance_authority @ DividendError::Unauthorized
+    )]
+    pub compliance_authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"dividend_pool", dividend_pool.property_mint.as_ref(), dividend_pool.payout_mint_seed()],
+        bump = dividend_pool.bump
+    )]
+    pub dividend_pool: Account<'info, DividendPool>,
+    
+    #[account(
+        init_if_needed,
+        payer = compliance_authority,
+        space = 8 + 32 + 32 + 2 + 2 + 8 + 1 + 32,
+        seeds = [b"tax_profile", dividend_pool.key().as_ref(), holder.as_ref()],
+        bump
+    )]
+    pub tax_profile: Account<'info, TaxProfile>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeTaxTokenVault<'info> {
+    #[account(
+        mut,
+        constraint = authority.key() == dividend_pool.authority @ DividendError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"dividend_pool", dividend_pool.property_mint.as_ref(), dividend_pool.payout_mint_seed()],
+        bump = dividend_pool.bump
+    )]
+    pub dividend_pool: Account<'info, DividendPool>,
+    
+    #[account(
+        constraint = Some(payout_mint.key()) == dividend_pool.payout_mint @ DividendError::InvalidMint
+    )]
+    pub payout_mint: Account<'info, Mint>,
+    
+    /// CHECK: PDA collecting withheld SOL; authority of the tax token vault
+    #[account(
+        seeds = [b"tax_vault", dividend_pool.key().as_ref()],
+        bump
+    )]
+    pub tax_vault: AccountInfo<'info>,
+    
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"tax_token_vault", dividend_pool.key().as_ref()],
+        bump,
+        token::mint = payout_mint,
+        token::authority = tax_vault
+    )]
+    pub tax_token_vault: Account<'info, TokenAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct RemitWithheldTax<'info> {
+    #[account(
+        constraint = compliance_authority.key() == dividend_pool.compliance_authority @ DividendError::Unauthorized
+    )]
+    pub compliance_authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"dividend_pool", dividend_pool.property_mint.as_ref(), dividend_pool.payout_mint_seed()],
+        bump = dividend_pool.bump
+    )]
+    pub dividend_pool: Account<'info, DividendPool>,
+    
+    /// CHECK: PDA collecting withheld SOL; authority of the tax token vault
+    #[account(
+        mut,
+        seeds = [b"tax_vault", dividend_pool.key().as_ref()],
+        bump
+    )]
+    pub tax_vault: AccountInfo<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"tax_token_vault", dividend_pool.key().as_ref()],
+        bump
+    )]
+    pub tax_token_vault: Option<Account<'info, TokenAccount>>,
+    
+    /// CHECK: Tax authority wallet receiving the remittance
+    #[account(mut)]
+    pub destination: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        constraint = destination_token_account.owner == destination.key() @ DividendError::InvalidTokenOwner
+    )]
+    pub destination_token_account: Option<Account<'info, TokenAccount>>,
+    
+    pub token_program: Option<Program<'info, Token>>,
+    pub system_program: Program<'info, System>,
 }
 
-#[account]
-#[derive(Default)]
-pub struct ClaimRecord {
-    /// User who claimed
-    pub user: Pubkey,
-    /// Distribution record
-    pub distribution: Pubkey,
-    /// Epoch number
-    pub epoch: u64,
-    /// Amount claimed
-    pub amount_claimed: u64,
-    /// Claim timestamp
-    pub claimed_at: i64,
-    /// Whether claimed
-    pub claimed: bool,
-    /// PDA bump
-    pub bump: u8,
+#[derive(Accounts)]
+#[instruction(depositor: Pubkey)]
+pub struct AddDepositor<'info> {
+    #[account(
+        mut,
+        constraint = authority.key() == dividend_pool.authority @ DividendError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"dividend_pool", dividend_pool.property_mint.as_ref(), dividend_pool.payout_mint_seed()],
+        bump = dividend_pool.bump
+    )]
+    pub dividend_pool: Account<'info, DividendPool>,
+    
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + 32 + 32 + 1 + 8 + 8 + 8 + 8 + 1 + 32,
+        seeds = [b"depositor", dividend_pool.key().as_ref(), depositor.as_ref()],
+        bump
+    )]
+    pub depositor_record: Account<'info, Depositor>,
+    
+    pub system_program: Program<'info, System>,
 }
 
-// ============================================================================
-// Contexts
-// ============================================================================
+#[derive(Accounts)]
+pub struct RemoveDepositor<'info> {
+    #[account(
+        constraint = authority.key() == dividend_pool.authority @ DividendError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"dividend_pool", dividend_pool.property_mint.as_ref(), dividend_pool.payout_mint_seed()],
+        bump = dividend_pool.bump
+    )]
+    pub dividend_pool: Account<'info, DividendPool>,
+    
+    #[account(
+        mut,
+        seeds = [b"depositor", dividend_pool.key().as_ref(), depositor_record.depositor.as_ref()],
+        bump = depositor_record.bump
+    )]
+    pub depositor_record: Account<'info, Depositor>,
+}
 
 #[derive(Accounts)]
-#[instruction(property_id: String)]
-pub struct InitializePool<'info> {
-    #[account(mut)]
+pub struct ConfigureWaterfall<'info> {
+    #[account(
+        mut,
+        constraint = authority.key() == dividend_pool.authority @ DividendError::Unauthorized,
+        constraint = authority.key() == preferred_pool.authority @ DividendError::Unauthorized
+    )]
     pub authority: Signer<'info>,
     
-    /// The property token mint
-    pub property_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        seeds = [b"dividend_pool", dividend_pool.property_mint.as_ref(), dividend_pool.payout_mint_seed()],
+        bump = dividend_pool.bump
+    )]
+    pub dividend_pool: Account<'info, DividendPool>,
     
     #[account(
-        init,
+        seeds = [b"dividend_pool", preferred_pool.property_mint.as_ref(), preferred_pool.payout_mint_seed()],
+        bump = preferred_pool.bump
+    )]
+    pub preferred_pool: Account<'info, DividendPool>,
+    
+    #[account(
+        init_if_needed,
         payer = authority,
-        space = 8 + 32 + 32 + 32 + 4 + 64 + 8 + 8 + 8 + 8 + 8 + 1 + 64,
-        seeds = [b"dividend_pool", property_mint.key().as_ref()],
+        space = 8 + 32 + 32 + 32 + 8 + 2 + 2 + 2 + 8 + 8 + 8 + 8 + 8 + 1 + 8 + 24,
+        seeds = [b"waterfall", dividend_pool.key().as_ref()],
         bump
     )]
+    pub waterfall: Account<'info, Waterfall>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct TransferPreferredAllocation<'info> {
+    #[account(
+        seeds = [b"dividend_pool", dividend_pool.property_mint.as_ref(), dividend_pool.payout_mint_seed()],
+        bump = dividend_pool.bump
+    )]
     pub dividend_pool: Account<'info, DividendPool>,
     
+    #[account(
+        mut,
+        seeds = [b"waterfall", dividend_pool.key().as_ref()],
+        bump = waterfall.bump
+    )]
+    pub waterfall: Account<'info, Waterfall>,
+    
+    #[account(
+        mut,
+        address = waterfall.preferred_pool @ DividendError::InvalidWaterfall
+    )]
+    pub preferred_pool: Account<'info, DividendPool>,
+    
     /// CHECK: PDA vault for holding SOL dividends
     #[account(
         mut,
@@ -334,39 +5313,133 @@ pub struct InitializePool<'info> {
     )]
     pub dividend_vault: AccountInfo<'info>,
     
+    /// CHECK: The preferred pool's SOL vault
+    #[account(
+        mut,
+        seeds = [b"dividend_vault", preferred_pool.key().as_ref()],
+        bump
+    )]
+    pub preferred_vault: AccountInfo<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"dividend_token_vault", dividend_pool.key().as_ref()],
+        bump
+    )]
+    pub token_vault: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"dividend_token_vault", preferred_pool.key().as_ref()],
+        bump
+    )]
+    pub preferred_token_vault: Option<Account<'info, TokenAccount>>,
+    
+    pub token_program: Option<Program<'info, Token>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetDeductions<'info> {
+    #[account(
+        constraint = authority.key() == dividend_pool.authority @ DividendError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"dividend_pool", dividend_pool.property_mint.as_ref(), dividend_pool.payout_mint_seed()],
+        bump = dividend_pool.bump
+    )]
+    pub dividend_pool: Account<'info, DividendPool>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeReserveTokenVault<'info> {
+    #[account(
+        mut,
+        constraint = authority.key() == dividend_pool.authority @ DividendError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"dividend_pool", dividend_pool.property_mint.as_ref(), dividend_pool.payout_mint_seed()],
+        bump = dividend_pool.bump
+    )]
+    pub dividend_pool: Account<'info, DividendPool>,
+    
+    #[account(
+        constraint = Some(payout_mint.key()) == dividend_pool.payout_mint @ DividendError::InvalidMint
+    )]
+    pub payout_mint: Account<'info, Mint>,
+    
+    /// CHECK: PDA accruing the reserve; authority of the reserve token vault
+    #[account(
+        seeds = [b"reserve_vault", dividend_pool.key().as_ref()],
+        bump
+    )]
+    pub reserve_vault: AccountInfo<'info>,
+    
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"reserve_token_vault", dividend_pool.key().as_ref()],
+        bump,
+        token::mint = payout_mint,
+        token::authority = reserve_vault
+    )]
+    pub reserve_token_vault: Account<'info, TokenAccount>,
+    
     pub system_program: Program<'info, System>,
     pub token_program: Program<'info, Token>,
     pub rent: Sysvar<'info, Rent>,
 }
 
 #[derive(Accounts)]
-pub struct DepositDividend<'info> {
+pub struct ReleaseReserve<'info> {
     #[account(
-        mut,
         constraint = authority.key() == dividend_pool.authority @ DividendError::Unauthorized
     )]
     pub authority: Signer<'info>,
     
     #[account(
         mut,
-        seeds = [b"dividend_pool", dividend_pool.property_mint.as_ref()],
+        seeds = [b"dividend_pool", dividend_pool.property_mint.as_ref(), dividend_pool.payout_mint_seed()],
         bump = dividend_pool.bump
     )]
     pub dividend_pool: Account<'info, DividendPool>,
     
-    /// CHECK: PDA vault for holding SOL dividends
+    /// CHECK: PDA accruing the reserve; authority of the reserve token vault
     #[account(
         mut,
-        seeds = [b"dividend_vault", dividend_pool.key().as_ref()],
+        seeds = [b"reserve_vault", dividend_pool.key().as_ref()],
         bump
     )]
-    pub dividend_vault: AccountInfo<'info>,
+    pub reserve_vault: AccountInfo<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"reserve_token_vault", dividend_pool.key().as_ref()],
+        bump
+    )]
+    pub reserve_token_vault: Option<Account<'info, TokenAccount>>,
+    
+    /// CHECK: Wallet receiving the released reserve
+    #[account(mut)]
+    pub destination: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        constraint = destination_token_account.owner == destination.key() @ DividendError::InvalidTokenOwner
+    )]
+    pub destination_token_account: Option<Account<'info, TokenAccount>>,
     
+    pub token_program: Option<Program<'info, Token>>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct StartDistribution<'info> {
+pub struct RegisterExcludedAccount<'info> {
     #[account(
         mut,
         constraint = authority.key() == dividend_pool.authority @ DividendError::Unauthorized
@@ -375,118 +5448,191 @@ pub struct StartDistribution<'info> {
     
     #[account(
         mut,
-        seeds = [b"dividend_pool", dividend_pool.property_mint.as_ref()],
+        seeds = [b"dividend_pool", dividend_pool.property_mint.as_ref(), dividend_pool.payout_mint_seed()],
         bump = dividend_pool.bump
     )]
     pub dividend_pool: Account<'info, DividendPool>,
     
-    pub property_mint: Account<'info, Mint>,
+    #[account(
+        constraint = token_account.mint == dividend_pool.property_mint @ DividendError::InvalidMint
+    )]
+    pub token_account: Account<'info, TokenAccount>,
     
     #[account(
         init,
         payer = authority,
-        space = 8 + 32 + 8 + 8 + 8 + 8 + 8 + 8 + 1 + 32,
-        seeds = [b"distribution", dividend_pool.key().as_ref(), &dividend_pool.current_epoch.to_le_bytes()],
+        space = 8 + 32 + 32 + 32 + 33 + 8 + 1 + 32,
+        seeds = [b"excluded", dividend_pool.key().as_ref(), token_account.key().as_ref()],
         bump
     )]
-    pub distribution_record: Account<'info, DistributionRecord>,
+    pub excluded_account: Account<'info, ExcludedAccount>,
     
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-#[instruction(epoch: u64)]
-pub struct ClaimDividend<'info> {
-    #[account(mut)]
-    pub user: Signer<'info>,
-    
+pub struct RegisterListingEscrow<'info> {
     #[account(
-        seeds = [b"dividend_pool", dividend_pool.property_mint.as_ref()],
-        bump = dividend_pool.bump
+        mut,
+        constraint = authority.key() == dividend_pool.authority @ DividendError::Unauthorized
     )]
-    pub dividend_pool: Account<'info, DividendPool>,
+    pub authority: Signer<'info>,
     
     #[account(
         mut,
-        seeds = [b"distribution", dividend_pool.key().as_ref(), &epoch.to_le_bytes()],
-        bump = distribution_record.bump
+        seeds = [b"dividend_pool", dividend_pool.property_mint.as_ref(), dividend_pool.payout_mint_seed()],
+        bump = dividend_pool.bump
     )]
-    pub distribution_record: Account<'info, DistributionRecord>,
+    pub dividend_pool: Account<'info, DividendPool>,
     
-    /// CHECK: PDA vault for holding SOL dividends
     #[account(
-        mut,
-        seeds = [b"dividend_vault", dividend_pool.key().as_ref()],
-        bump
+        seeds = [b"listing", listing.seller.as_ref(), listing.token_mint.as_ref()],
+        seeds::program = marketplace::ID,
+        bump = listing.bump,
+        constraint = listing.token_mint == dividend_pool.property_mint @ DividendError::InvalidMint
     )]
-    pub dividend_vault: AccountInfo<'info>,
+    pub listing: Account<'info, marketplace::Listing>,
     
-    /// User's property token account
     #[account(
-        constraint = user_token_account.owner == user.key() @ DividendError::InvalidTokenOwner,
-        constraint = user_token_account.mint == dividend_pool.property_mint @ DividendError::InvalidMint
+        associated_token::mint = dividend_pool.property_mint,
+        associated_token::authority = listing
     )]
-    pub user_token_account: Account<'info, TokenAccount>,
+    pub escrow_token_account: Account<'info, TokenAccount>,
     
     #[account(
         init,
-        payer = user,
-        space = 8 + 32 + 32 + 8 + 8 + 8 + 1 + 1 + 32,
-        seeds = [b"claim", distribution_record.key().as_ref(), user.key().as_ref()],
+        payer = authority,
+        space = 8 + 32 + 32 + 32 + 33 + 8 + 1 + 32,
+        seeds = [b"excluded", dividend_pool.key().as_ref(), escrow_token_account.key().as_ref()],
         bump
     )]
-    pub claim_record: Account<'info, ClaimRecord>,
+    pub excluded_account: Account<'info, ExcludedAccount>,
     
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-#[instruction(epoch: u64)]
-pub struct SetDistributionTotalClaimed<'info> {
-    #[account(mut)]
+pub struct RemoveExcludedAccount<'info> {
+    #[account(
+        mut,
+        constraint = authority.key() == dividend_pool.authority @ DividendError::Unauthorized
+    )]
     pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"dividend_pool", dividend_pool.property_mint.as_ref(), dividend_pool.payout_mint_seed()],
+        bump = dividend_pool.bump
+    )]
+    pub dividend_pool: Account<'info, DividendPool>,
+    
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"excluded", dividend_pool.key().as_ref(), excluded_account.token_account.as_ref()],
+        bump = excluded_account.bump
+    )]
+    pub excluded_account: Account<'info, ExcludedAccount>,
+}
 
+#[derive(Accounts)]
+pub struct UpdateAuthority<'info> {
     #[account(
-        seeds = [b"dividend_pool", dividend_pool.property_mint.as_ref()],
-        bump = dividend_pool.bump,
+        mut,
         constraint = authority.key() == dividend_pool.authority @ DividendError::Unauthorized
     )]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"dividend_pool", dividend_pool.property_mint.as_ref(), dividend_pool.payout_mint_seed()],
+        bump = dividend_pool.bump
+    )]
+    pub dividend_pool: Account<'info, DividendPool>,
+}
+
+#[derive(Accounts)]
+pub struct ReassignPoolAuthority<'info> {
+    pub admin: Signer<'info>,
+    
+    /// CHECK: Crowdfunding platform config; address and layout are verified in the handler
+    pub platform_config: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"dividend_pool", dividend_pool.property_mint.as_ref(), dividend_pool.payout_mint_seed()],
+        bump = dividend_pool.bump
+    )]
     pub dividend_pool: Account<'info, DividendPool>,
+}
 
+#[derive(Accounts)]
+pub struct SkipEpochs<'info> {
+    #[account(mut)]
+    pub holder: Signer<'info>,
+    
+    #[account(
+        seeds = [b"dividend_pool", dividend_pool.property_mint.as_ref(), dividend_pool.payout_mint_seed()],
+        bump = dividend_pool.bump
+    )]
+    pub dividend_pool: Account<'info, DividendPool>,
+    
     #[account(
         mut,
-        seeds = [b"distribution", dividend_pool.key().as_ref(), &epoch.to_le_bytes()],
-        bump = distribution_record.bump
+        seeds = [b"cursor", dividend_pool.key().as_ref(), holder.key().as_ref()],
+        bump = holder_cursor.bump
+    )]
+    pub holder_cursor: Account<'info, HolderCursor>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigratePool<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub property_mint: Account<'info, Mint>,
+    
+    /// CHECK: Pool in its legacy layout; owner, size, discriminator and authority
+    /// are verified in the handler
+    #[account(
+        mut,
+        seeds = [b"dividend_pool", property_mint.key().as_ref()],
+        bump
     )]
-    pub distribution_record: Account<'info, DistributionRecord>,
-}
-
-#[derive(Accounts)]
-#[instruction(epoch: u64)]
-pub struct GetClaimableAmount<'info> {
-    pub user: Signer<'info>,
+    pub dividend_pool: UncheckedAccount<'info>,
     
+    /// CHECK: PDA vault for holding SOL dividends
     #[account(
-        seeds = [b"dividend_pool", dividend_pool.property_mint.as_ref()],
-        bump = dividend_pool.bump
+        mut,
+        seeds = [b"dividend_vault", dividend_pool.key().as_ref()],
+        bump
     )]
-    pub dividend_pool: Account<'info, DividendPool>,
+    pub dividend_vault: AccountInfo<'info>,
     
+    /// CHECK: PDA collecting withheld SOL
     #[account(
-        seeds = [b"distribution", dividend_pool.key().as_ref(), &epoch.to_le_bytes()],
-        bump = distribution_record.bump
+        mut,
+        seeds = [b"tax_vault", dividend_pool.key().as_ref()],
+        bump
     )]
-    pub distribution_record: Account<'info, DistributionRecord>,
+    pub tax_vault: AccountInfo<'info>,
     
+    /// CHECK: PDA accruing the reserve
     #[account(
-        constraint = user_token_account.owner == user.key() @ DividendError::InvalidTokenOwner,
-        constraint = user_token_account.mint == dividend_pool.property_mint @ DividendError::InvalidMint
+        mut,
+        seeds = [b"reserve_vault", dividend_pool.key().as_ref()],
+        bump
     )]
-    pub user_token_account: Account<'info, TokenAccount>,
+    pub reserve_vault: AccountInfo<'info>,
+    
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct UpdateAuthority<'info> {
+#[instruction(epoch: u64)]
+pub struct MigrateDistribution<'info> {
     #[account(
         mut,
         constraint = authority.key() == dividend_pool.authority @ DividendError::Unauthorized
@@ -494,11 +5640,21 @@ pub struct UpdateAuthority<'info> {
     pub authority: Signer<'info>,
     
     #[account(
-        mut,
-        seeds = [b"dividend_pool", dividend_pool.property_mint.as_ref()],
+        seeds = [b"dividend_pool", dividend_pool.property_mint.as_ref(), dividend_pool.payout_mint_seed()],
         bump = dividend_pool.bump
     )]
     pub dividend_pool: Account<'info, DividendPool>,
+    
+    /// CHECK: Distribution in its legacy layout; owner, size and discriminator are
+    /// verified in the handler
+    #[account(
+        mut,
+        seeds = [b"distribution", dividend_pool.key().as_ref(), &epoch.to_le_bytes()],
+        bump
+    )]
+    pub distribution_record: UncheckedAccount<'info>,
+    
+    pub system_program: Program<'info, System>,
 }
 
 // ============================================================================
@@ -510,6 +5666,7 @@ pub struct PoolInitialized {
     pub pool: Pubkey,
     pub property_mint: Pubkey,
     pub authority: Pubkey,
+    pub payout_mint: Option<Pubkey>,
 }
 
 #[event]
@@ -525,22 +5682,253 @@ pub struct DistributionStarted {
     pub pool: Pubkey,
     pub epoch: u64,
     pub total_amount: u64,
-    pub amount_per_token: u64,
+    pub amount_per_token: u128,
+    pub snapshot_slot: u64,
+    pub snapshot_root: [u8; 32],
+    pub carried_forward: u64,
+    pub excluded_supply: u64,
+    pub started_by: Pubkey,
+    pub management_fee: u64,
+    pub reserve: u64,
+    pub preferred_amount: u64,
+}
+
+#[event]
+pub struct SnapshotRootPosted {
+    pub pool: Pubkey,
+    pub epoch: u64,
+    pub snapshot_slot: u64,
+    pub snapshot_root: [u8; 32],
+}
+
+#[event]
+pub struct MerkleDistributionStarted {
+    pub pool: Pubkey,
+    pub epoch: u64,
+    pub total_amount: u64,
+    pub merkle_root: [u8; 32],
+    pub holder_count: u32,
+    pub management_fee: u64,
+    pub reserve: u64,
 }
 
 #[event]
 pub struct DividendClaimed {
     pub pool: Pubkey,
     pub user: Pubkey,
+    pub recipient: Pubkey,
+    pub epoch: u64,
+    pub amount: u64,
+    pub withheld: u64,
+    pub net: u64,
+}
+
+#[event]
+pub struct DividendReinvested {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub epoch: u64,
+    pub amount: u64,
+    pub listing: Pubkey,
+    pub withheld: u64,
+    pub tokens: u64,
+    pub spent: u64,
+    pub refunded: u64,
+}
+
+#[event]
+pub struct DividendRangeClaimed {
+    pub pool: Pubkey,
+    pub holder: Pubkey,
+    pub from_epoch: u64,
+    pub to_epoch: u64,
+    pub amount: u64,
+}
+
+#[event]
+pub struct PushEntrySkipped {
+    pub pool: Pubkey,
+    pub epoch: u64,
+    pub holder: Pubkey,
+    pub error_code: u32,
+}
+
+#[event]
+pub struct DividendsPushed {
+    pub pool: Pubkey,
     pub epoch: u64,
+    pub keeper: Pubkey,
+    pub paid: u32,
+    pub skipped: u32,
+    pub amount: u64,
+}
+
+#[event]
+pub struct StreamingEnabled {
+    pub pool: Pubkey,
+    pub stream_period: i64,
+}
+
+#[event]
+pub struct StreamFunded {
+    pub pool: Pubkey,
+    pub amount: u64,
+    pub reward_rate: u128,
+    pub period_finish: i64,
+}
+
+#[event]
+pub struct HolderCheckpointed {
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub balance: u64,
+    pub total_checkpointed: u64,
+}
+
+#[event]
+pub struct StreamClaimed {
+    pub pool: Pubkey,
+    pub owner: Pubkey,
     pub amount: u64,
+    pub withheld: u64,
+    pub net: u64,
+}
+
+#[event]
+pub struct DistributionVoided {
+    pub pool: Pubkey,
+    pub epoch: u64,
+    pub amount_returned: u64,
+    pub reason_hash: [u8; 32],
+}
+
+#[event]
+pub struct CorrectiveDistributionStarted {
+    pub pool: Pubkey,
+    pub epoch: u64,
+    pub corrects_epoch: u64,
+    pub total_amount: u64,
+    pub amount_per_token: u128,
+    pub reason_hash: [u8; 32],
 }
 
 #[event]
-pub struct DistributionClaimedTotalSynced {
+pub struct TotalClaimedRecomputed {
     pub pool: Pubkey,
     pub epoch: u64,
+    pub previous_total_claimed: u64,
     pub total_claimed: u64,
+    pub claim_records: u32,
+    pub reason_hash: [u8; 32],
+}
+
+#[event]
+pub struct TotalClaimedRecomputeProgress {
+    pub pool: Pubkey,
+    pub epoch: u64,
+    pub records_counted: u32,
+    pub claim_records: u32,
+}
+
+#[event]
+pub struct UnclaimedPolicyUpdated {
+    pub pool: Pubkey,
+    pub claim_period_days: u64,
+    pub policy: UnclaimedPolicy,
+    pub treasury: Pubkey,
+}
+
+#[event]
+pub struct UnclaimedSwept {
+    pub pool: Pubkey,
+    pub epoch: u64,
+    pub amount: u64,
+    pub treasury: Option<Pubkey>,
+}
+
+#[event]
+pub struct PayoutPreferenceUpdated {
+    pub pool: Pubkey,
+    pub holder: Pubkey,
+    pub payout_address: Option<Pubkey>,
+    pub delegate: Option<Pubkey>,
+}
+
+#[event]
+pub struct ComplianceAuthorityUpdated {
+    pub pool: Pubkey,
+    pub old_compliance_authority: Pubkey,
+    pub new_compliance_authority: Pubkey,
+}
+
+#[event]
+pub struct TaxProfileUpdated {
+    pub pool: Pubkey,
+    pub holder: Pubkey,
+    pub withholding_bps: u16,
+    pub jurisdiction: [u8; 2],
+}
+
+#[event]
+pub struct WithheldTaxRemitted {
+    pub pool: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct WaterfallConfigured {
+    pub pool: Pubkey,
+    pub preferred_pool: Pubkey,
+    pub preferred_mint: Pubkey,
+    pub preferred_capital: u64,
+    pub preferred_return_bps: u16,
+    pub catch_up_bps: u16,
+    pub residual_preferred_bps: u16,
+}
+
+#[event]
+pub struct PreferredAllocationTransferred {
+    pub pool: Pubkey,
+    pub preferred_pool: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct DepositorUpdated {
+    pub pool: Pubkey,
+    pub depositor: Pubkey,
+    pub active: bool,
+}
+
+#[event]
+pub struct DeductionsUpdated {
+    pub pool: Pubkey,
+    pub management_fee_bps: u16,
+    pub reserve_bps: u16,
+    pub manager: Pubkey,
+}
+
+#[event]
+pub struct ReserveReleased {
+    pub pool: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub justification_hash: [u8; 32],
+}
+
+#[event]
+pub struct ExcludedAccountRegistered {
+    pub pool: Pubkey,
+    pub token_account: Pubkey,
+    pub owner: Pubkey,
+    pub beneficiary: Option<Pubkey>,
+}
+
+#[event]
+pub struct ExcludedAccountRemoved {
+    pub pool: Pubkey,
+    pub token_account: Pubkey,
 }
 
 #[event]
@@ -550,6 +5938,37 @@ pub struct AuthorityUpdated {
     pub new_authority: Pubkey,
 }
 
+#[event]
+pub struct PoolAuthorityReassigned {
+    pub pool: Pubkey,
+    pub old_authority: Pubkey,
+    pub new_authority: Pubkey,
+    pub admin: Pubkey,
+}
+
+#[event]
+pub struct EpochsSkipped {
+    pub pool: Pubkey,
+    pub holder: Pubkey,
+    pub from_epoch: u64,
+    pub to_epoch: u64,
+    pub forfeited: u32,
+}
+
+#[event]
+pub struct PoolMigrated {
+    pub pool: Pubkey,
+    pub authority: Pubkey,
+}
+
+#[event]
+pub struct DistributionMigrated {
+    pub pool: Pubkey,
+    pub epoch: u64,
+    pub amount_per_token: u128,
+    pub claim_count: u32,
+}
+
 // ============================================================================
 // Errors
 // ============================================================================
@@ -580,4 +5999,374 @@ pub enum DividendError {
     InvalidTokenOwner,
     #[msg("Invalid token mint")]
     InvalidMint,
+    #[msg("Signer is not the issuer of this property mint")]
+    NotPropertyIssuer,
+    #[msg("Invalid crowdfunding account")]
+    InvalidCrowdfundingAccount,
+    #[msg("Invalid balance snapshot")]
+    InvalidSnapshot,
+    #[msg("Snapshot balance proof does not match the distribution")]
+    InvalidSnapshotProof,
+    #[msg("Instruction does not match the distribution mode")]
+    WrongDistributionMode,
+    #[msg("Invalid holder count or index")]
+    InvalidHolderCount,
+    #[msg("Distribution exceeds deposited dividends")]
+    InsufficientDeposits,
+    #[msg("Excluded accounts do not match the pool's registered set")]
+    ExcludedAccountsMismatch,
+    #[msg("Payout token accounts do not match the pool's payout asset")]
+    InvalidPayoutAccounts,
+    #[msg("Distribution interval has not elapsed")]
+    DistributionNotDue,
+    #[msg("Snapshot root has not been posted for this distribution")]
+    SnapshotPending,
+    #[msg("Snapshot root already posted")]
+    SnapshotAlreadyPosted,
+    #[msg("Claim period for this distribution has expired")]
+    ClaimExpired,
+    #[msg("Claim period for this distribution has not ended")]
+    ClaimPeriodOpen,
+    #[msg("Unclaimed dividends already swept")]
+    AlreadySwept,
+    #[msg("Invalid treasury")]
+    InvalidTreasury,
+    #[msg("Claim range does not match the holder's unclaimed epochs")]
+    InvalidClaimRange,
+    #[msg("Instruction does not match the pool's distribution mode")]
+    WrongPoolMode,
+    #[msg("Invalid stream period")]
+    InvalidStreamPeriod,
+    #[msg("Listing price exceeds the maximum price")]
+    PriceTooHigh,
+    #[msg("Dividend is too small to buy a whole token")]
+    ReinvestmentTooSmall,
+    #[msg("Withholding rate cannot exceed 100%")]
+    InvalidWithholdingRate,
+    #[msg("Management fee and reserve cannot exceed 100%")]
+    InvalidDeductions,
+    #[msg("Manager does not match the pool's manager")]
+    InvalidManager,
+    #[msg("Invalid waterfall configuration")]
+    InvalidWaterfall,
+    #[msg("Waterfall account does not match the pool")]
+    WaterfallMismatch,
+    #[msg("Distribution has been voided")]
+    DistributionVoided,
+    #[msg("Distribution already has claims")]
+    ClaimsStarted,
+    #[msg("Claim records do not cover the distribution")]
+    IncompleteClaimRecords,
+    #[msg("Payout address does not match the holder's payout preference")]
+    InvalidPayoutAddress,
+    #[msg("Push batch accounts do not match the holders")]
+    InvalidPushBatch,
+    #[msg("Amount exceeds the reserve balance")]
+    InsufficientReserve,
+    #[msg("A justification hash is required")]
+    MissingJustification,
+    #[msg("Snapshot total does not match the distribution's circulating supply")]
+    SnapshotTotalMismatch,
+    #[msg("Pool already has the maximum number of excluded accounts")]
+    TooManyExcludedAccounts,
+    #[msg("Tax vault holds less than the requested amount")]
+    InsufficientTaxBalance,
+    #[msg("Account is not in its legacy layout")]
+    NotLegacyAccount,
+    #[msg("The first epoch of the range cannot be settled by a range claim")]
+    ClaimRangeBlocked,
+    #[msg("Claims were made since the recompute started; start it again")]
+    RecomputeOutdated,
+    #[msg("Recomputed total is below the distribution's total claimed")]
+    RecomputeBelowTotalClaimed,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool_with_deductions(management_fee_bps: u16, reserve_bps: u16) -> DividendPool {
+        DividendPool {
+            management_fee_bps,
+            reserve_bps,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn merkle_distributions_take_deductions() {
+        let mut pool = pool_with_deductions(1_000, 500);
+        pool.record_deposit(100).unwrap();
+
+        // Only 85 is left after the 10 fee and 5 reserve
+        assert!(pool.clone().fund_merkle_distribution(100).is_err());
+
+        let deductions = pool.fund_merkle_distribution(85).unwrap();
+        assert_eq!((deductions.management_fee, deductions.reserve), (10, 5));
+        assert_eq!(pool.total_deposited_current_epoch, 0);
+        assert_eq!(pool.deductible_deposits, 0);
+        assert_eq!(pool.reserve_balance, 5);
+    }
+
+    #[test]
+    fn deposit_merkle_deposit_start() {
+        let mut pool = pool_with_deductions(1_000, 2_000);
+        pool.record_deposit(100).unwrap();
+        pool.fund_merkle_distribution(70).unwrap();
+        pool.record_deposit(10).unwrap();
+
+        // Deductions of the next distribution apply to the new 10 only
+        let deductions = pool.take_deductions().unwrap();
+        assert_eq!((deductions.management_fee, deductions.reserve), (1, 2));
+        assert_eq!(pool.total_deposited_current_epoch, 7);
+        assert_eq!(pool.reserve_balance, 22);
+    }
+
+    #[test]
+    fn deductions_skip_carried_over_amounts() {
+        let mut pool = pool_with_deductions(1_000, 0);
+        pool.record_deposit(50).unwrap();
+        pool.take_deductions().unwrap();
+        pool.total_deposited_current_epoch += 30;
+
+        let deductions = pool.take_deductions().unwrap();
+        assert_eq!(deductions.management_fee, 0);
+        assert_eq!(pool.total_deposited_current_epoch, 75);
+    }
+
+    fn waterfall(
+        preferred_capital: u64,
+        preferred_return_bps: u16,
+        catch_up_bps: u16,
+        residual_preferred_bps: u16,
+    ) -> Waterfall {
+        Waterfall {
+            preferred_capital,
+            preferred_return_bps,
+            catch_up_bps,
+            residual_preferred_bps,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn waterfall_pays_accrued_preferred_return_first() {
+        // 8% a year on 1_000_000
+        let mut waterfall = waterfall(1_000_000, 800, 0, 0);
+        waterfall.accrue(SECONDS_PER_YEAR / 2).unwrap();
+        assert_eq!(waterfall.accrued_preferred_return, 40_000);
+
+        // A short deposit leaves the rest of the return owed
+        assert_eq!(waterfall.allocate(50_000, SECONDS_PER_YEAR).unwrap(), 50_000);
+        assert_eq!(waterfall.accrued_preferred_return, 30_000);
+        assert_eq!(waterfall.common_allocated, 0);
+
+        assert_eq!(waterfall.allocate(100_000, SECONDS_PER_YEAR).unwrap(), 30_000);
+        assert_eq!(waterfall.accrued_preferred_return, 0);
+        assert_eq!(waterfall.common_allocated, 70_000);
+        assert_eq!(waterfall.preferred_pending, 80_000);
+    }
+
+    #[test]
+    fn waterfall_catch_up_restores_common_share() {
+        // 10% return, common caught up to 20% of the total, residual split evenly
+        let mut waterfall = waterfall(1_000_000, 1_000, 2_000, 5_000);
+
+        // 100_000 return, then 25_000 catch-up, then 75_000 split evenly
+        assert_eq!(waterfall.allocate(200_000, SECONDS_PER_YEAR).unwrap(), 137_500);
+        assert_eq!(waterfall.common_allocated, 62_500);
+        assert_eq!(waterfall.preferred_allocated, 137_500);
+    }
+
+    #[test]
+    fn waterfall_catch_up_spans_allocations() {
+        let mut waterfall = waterfall(1_000_000, 1_000, 2_000, 5_000);
+
+        // Only 10_000 of the 25_000 catch-up fits
+        assert_eq!(waterfall.allocate(110_000, SECONDS_PER_YEAR).unwrap(), 100_000);
+        assert_eq!(waterfall.common_allocated, 10_000);
+
+        // The remaining 15_000 goes to common before the residual split
+        assert_eq!(waterfall.allocate(20_000, SECONDS_PER_YEAR).unwrap(), 2_500);
+        assert_eq!(waterfall.common_allocated, 27_500);
+
+        // Caught up exactly: the next allocation is all residual
+        assert_eq!(waterfall.allocate(10_000, SECONDS_PER_YEAR).unwrap(), 5_000);
+    }
+
+    #[test]
+    fn waterfall_residual_rounds_toward_common() {
+        let mut waterfall = waterfall(0, 0, 0, 2_500);
+        assert_eq!(waterfall.allocate(1_000, 0).unwrap(), 250);
+        assert_eq!(waterfall.allocate(3, 0).unwrap(), 0);
+        assert_eq!(waterfall.common_allocated, 753);
+        assert_eq!(waterfall.preferred_allocated + waterfall.common_allocated, 1_003);
+    }
+
+    #[test]
+    fn waterfall_does_not_split_returned_common_allocation_again() {
+        let mut waterfall = waterfall(1_000_000, 1_000, 2_000, 5_000);
+        assert_eq!(waterfall.split(200_000, SECONDS_PER_YEAR).unwrap(), 137_500);
+
+        // The common distribution is voided and its 62_500 re-split with a new
+        // 10_000 deposit: only the deposit runs through the tiers
+        waterfall.return_common(62_500).unwrap();
+        assert_eq!(waterfall.split(72_500, SECONDS_PER_YEAR).unwrap(), 5_000);
+        assert_eq!(waterfall.common_allocated, 67_500);
+        assert_eq!(waterfall.preferred_allocated, 142_500);
+        assert_eq!(waterfall.common_carried, 0);
+    }
+
+    fn stream(stream_period: i64) -> StreamState {
+        StreamState {
+            stream_period,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn stream_keeps_payout_streamed_while_nothing_is_checkpointed() {
+        let mut stream = stream(100);
+        stream.fund(1_000, 0).unwrap();
+        stream.accrue(50).unwrap();
+        assert_eq!(stream.reward_per_token, 0);
+        assert_eq!(stream.unallocated, 500 * AMOUNT_PER_TOKEN_SCALE);
+
+        let mut position = StreamPosition::default();
+        position.checkpoint(&mut stream, 10).unwrap();
+        assert_eq!(stream.total_checkpointed, 10);
+        stream.accrue(100).unwrap();
+        position.checkpoint(&mut stream, 10).unwrap();
+        assert_eq!(position.accrued, 500);
+
+        // A deposit of nothing streams what was unallocated again
+        stream.fund(0, 100).unwrap();
+        stream.accrue(200).unwrap();
+        position.checkpoint(&mut stream, 10).unwrap();
+        assert_eq!(position.accrued, 1_000);
+        assert_eq!(stream.unallocated, 0);
+    }
+
+    #[test]
+    fn stream_fund_carries_over_the_rest_of_the_stream() {
+        let mut stream = stream(100);
+        stream.total_checkpointed = 1;
+        stream.fund(1_000, 0).unwrap();
+        stream.fund(1_000, 50).unwrap();
+        assert_eq!(stream.period_finish, 150);
+        assert_eq!(stream.reward_rate, 15 * AMOUNT_PER_TOKEN_SCALE);
+
+        // Accrual stops at the end of the stream
+        stream.accrue(1_000).unwrap();
+        assert_eq!(stream.last_update, 150);
+        assert_eq!(entitlement(1, stream.reward_per_token).unwrap(), 2_000);
+    }
+
+    #[test]
+    fn stream_rounding_is_kept_unallocated() {
+        let mut stream = stream(3);
+        stream.total_checkpointed = 1;
+        stream.fund(1, 0).unwrap();
+        stream.accrue(3).unwrap();
+
+        assert_eq!(entitlement(1, stream.reward_per_token).unwrap(), 0);
+        assert_eq!(stream.reward_per_token + stream.unallocated, AMOUNT_PER_TOKEN_SCALE);
+    }
+
+    #[test]
+    fn stream_position_settles_once_per_accumulator_step() {
+        let mut stream = stream(100);
+        let mut position = StreamPosition::default();
+        position.checkpoint(&mut stream, 10).unwrap();
+
+        stream.reward_per_token = 3 * AMOUNT_PER_TOKEN_SCALE;
+        position.checkpoint(&mut stream, 10).unwrap();
+        assert_eq!(position.accrued, 30);
+
+        position.checkpoint(&mut stream, 10).unwrap();
+        assert_eq!(position.accrued, 30);
+
+        stream.reward_per_token = AMOUNT_PER_TOKEN_SCALE;
+        assert!(position.checkpoint(&mut stream, 10).is_err());
+    }
+
+    #[test]
+    fn checkpointed_transfers_split_the_stream_exactly() {
+        let mut stream = stream(100);
+        let (mut seller, mut buyer) = (StreamPosition::default(), StreamPosition::default());
+        seller.checkpoint(&mut stream, 10).unwrap();
+        buyer.checkpoint(&mut stream, 0).unwrap();
+        stream.fund(1_000, 0).unwrap();
+
+        // Half the stream in, the seller checkpoints, sends 4 tokens, and both
+        // sides checkpoint after the transfer
+        stream.accrue(50).unwrap();
+        seller.checkpoint(&mut stream, 10).unwrap();
+        seller.checkpoint(&mut stream, 6).unwrap();
+        buyer.checkpoint(&mut stream, 4).unwrap();
+        assert_eq!(stream.total_checkpointed, 10);
+
+        stream.accrue(100).unwrap();
+        seller.checkpoint(&mut stream, 6).unwrap();
+        buyer.checkpoint(&mut stream, 4).unwrap();
+        assert_eq!((seller.accrued, buyer.accrued), (800, 200));
+        assert_eq!(stream.unallocated, 0);
+    }
+
+    #[test]
+    fn tokens_leaving_without_a_checkpoint_give_back_their_accrual() {
+        let mut stream = stream(100);
+        let (mut seller, mut buyer) = (StreamPosition::default(), StreamPosition::default());
+        seller.checkpoint(&mut stream, 10).unwrap();
+        stream.fund(1_000, 0).unwrap();
+
+        // All 10 tokens move at some point before the seller's next checkpoint,
+        // and the buyer checkpoints them while the seller's balance still counts
+        stream.accrue(50).unwrap();
+        buyer.checkpoint(&mut stream, 10).unwrap();
+        assert_eq!(stream.total_checkpointed, 20);
+
+        stream.accrue(100).unwrap();
+        seller.checkpoint(&mut stream, 0).unwrap();
+        buyer.checkpoint(&mut stream, 10).unwrap();
+
+        // The seller keeps nothing, the buyer earns on half the stream's weight
+        // from its checkpoint, and the rest is streamed again
+        assert_eq!((seller.accrued, buyer.accrued), (0, 250));
+        assert_eq!(stream.unallocated, 750 * AMOUNT_PER_TOKEN_SCALE);
+        assert_eq!(stream.total_checkpointed, 10);
+    }
+
+    #[test]
+    fn entitlement_rounds_down_and_rejects_overflow() {
+        assert_eq!(entitlement(100, AMOUNT_PER_TOKEN_SCALE * 5 / 2).unwrap(), 250);
+        assert_eq!(entitlement(3, AMOUNT_PER_TOKEN_SCALE / 3).unwrap(), 0);
+        assert_eq!(entitlement(0, u128::MAX).unwrap(), 0);
+
+        assert!(entitlement(u64::MAX, 2 * AMOUNT_PER_TOKEN_SCALE).is_err());
+        assert!(entitlement(u64::MAX, u128::MAX).is_err());
+    }
+
+    #[test]
+    fn claim_bitmap_rounds_up_to_whole_bytes() {
+        assert_eq!(ClaimBitmap::bytes_for(0), 0);
+        assert_eq!(ClaimBitmap::bytes_for(1), 1);
+        assert_eq!(ClaimBitmap::bytes_for(8), 1);
+        assert_eq!(ClaimBitmap::bytes_for(9), 2);
+        assert_eq!(ClaimBitmap::bytes_for(u32::MAX), 1 << 29);
+    }
+
+    #[test]
+    fn claim_bitmap_sets_bits_across_byte_boundaries() {
+        let mut bitmap = ClaimBitmap {
+            claimed: vec![0; ClaimBitmap::bytes_for(9)],
+            ..Default::default()
+        };
+        bitmap.set_claimed(7);
+        bitmap.set_claimed(8);
+
+        assert_eq!(bitmap.claimed, vec![0x80, 0x01]);
+        assert!(bitmap.is_claimed(7) && bitmap.is_claimed(8));
+        assert!(!bitmap.is_claimed(6) && !bitmap.is_claimed(0));
+    }
 }
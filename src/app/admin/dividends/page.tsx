@@ -25,6 +25,7 @@ import {
   fetchDividendPool,
   getDividendPoolPDA,
   DividendPool,
+  DistributionSnapshot,
 } from "@/lib/dividendClient";
 import {
   fetchCampaign,
@@ -42,6 +43,8 @@ interface PropertyWithDividend {
   /** Funded campaigns require ≥1 investor to have called claim_tokens before dividends */
   dividendEligibilityOk: boolean;
   dividendEligibilityReason?: string;
+  /** Balance snapshot for the next distribution, built off-chain. */
+  snapshot?: DistributionSnapshot;
 }
 
 export default function AdminDividendsPage() {
@@ -240,10 +243,17 @@ export default function AdminDividendsPage() {
         return;
       }
       
+      if (!selectedProperty.snapshot) {
+        setError("No balance snapshot is available for this distribution yet");
+        setProcessing(false);
+        return;
+      }
+      
       const signature = await startDistribution(
         { publicKey, signTransaction },
         mint,
-        pool.currentEpoch
+        pool.currentEpoch,
+        selectedProperty.snapshot
       );
       
       setTxSignature(signature);
@@ -53,6 +53,7 @@ import {
   fetchUserDividendMintSummary,
   fetchUserDividendClaimHistory,
   getExplorerUrl,
+  SnapshotClaim,
 } from "@/lib/dividendClient";
 import { LAMPORTS_PER_SOL, PublicKey } from "@solana/web3.js";
 
@@ -121,6 +122,8 @@ type DividendClaimRow = {
   mint: string;
   epoch: number;
   claimableSol: number;
+  /** Snapshot balance and proof the claim is made against. */
+  snapshotClaim?: SnapshotClaim;
 };
 
 type DividendHistoryRow = {
@@ -492,10 +495,14 @@ export default function DashboardPage() {
     setActionSuccess(null);
     setLastActionTxSignature(null);
     try {
+      if (!row.snapshotClaim) {
+        throw new Error("No snapshot proof is available for this epoch yet");
+      }
       const sig = await claimDividend(
         { publicKey, signTransaction },
         new PublicKey(row.mint),
-        row.epoch
+        row.epoch,
+        row.snapshotClaim
       );
       setActionSuccess(
         `Claimed ${row.claimableSol.toFixed(6)} SOL for epoch ${row.epoch} (${row.propertyName}).`
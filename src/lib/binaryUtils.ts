@@ -30,3 +30,10 @@ export function i64LE(value: bigint | number): Buffer {
   }
   return u64LE(v);
 }
+
+/** Read a little-endian u128 (e.g. `amount_per_token`) at `offset`. */
+export function readU128LE(buf: Buffer, offset: number): bigint {
+  const lo = buf.readBigUInt64LE(offset);
+  const hi = buf.readBigUInt64LE(offset + 8);
+  return (hi << BigInt(64)) | lo;
+}
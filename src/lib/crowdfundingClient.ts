@@ -3,6 +3,7 @@ import {
   PublicKey,
   Transaction,
   SystemProgram,
+  TransactionInstruction,
   LAMPORTS_PER_SOL,
 } from "@solana/web3.js";
 import {
@@ -13,6 +14,13 @@ import {
 } from "@solana/spl-token";
 import { connection, SOLANA_NETWORK } from "./solana";
 import { u64LE, i64LE } from "./binaryUtils";
+import {
+  DIVIDEND_PROGRAM_ID,
+  getDividendPoolPDA,
+  getDividendVaultPDA,
+  getTaxVaultPDA,
+  getReserveVaultPDA,
+} from "./dividendClient";
 
 // Program ID - Update this after deploying the contract
 export const CROWDFUNDING_PROGRAM_ID = new PublicKey(
@@ -109,6 +117,49 @@ export function getEscrowVaultPDA(campaign: PublicKey): [PublicKey, number] {
   );
 }
 
+export function getBondVaultPDA(campaign: PublicKey): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("bond"), campaign.toBuffer()],
+    CROWDFUNDING_PROGRAM_ID
+  );
+}
+
+/** Canonical property identifier used by the on-chain property registry. */
+export function normalizePropertyId(propertyId: string): string {
+  return propertyId.trim().replace(/[A-Z]/g, (c) => c.toLowerCase());
+}
+
+/** PDA: `["property", sha256(normalizePropertyId(propertyId))]` */
+export async function getPropertyRegistryPDA(
+  propertyId: string
+): Promise<[PublicKey, number]> {
+  const digest = await crypto.subtle.digest(
+    "SHA-256",
+    new TextEncoder().encode(normalizePropertyId(propertyId))
+  );
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("property"), Buffer.from(digest)],
+    CROWDFUNDING_PROGRAM_ID
+  );
+}
+
+export function getAffiliatePDA(wallet: PublicKey): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("affiliate"), wallet.toBuffer()],
+    CROWDFUNDING_PROGRAM_ID
+  );
+}
+
+export function getReferralRecordPDA(
+  campaign: PublicKey,
+  affiliateWallet: PublicKey
+): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("referral"), campaign.toBuffer(), affiliateWallet.toBuffer()],
+    CROWDFUNDING_PROGRAM_ID
+  );
+}
+
 export function getInvestorRecordPDA(
   campaign: PublicKey,
   investor: PublicKey
@@ -218,6 +269,8 @@ export async function createCampaignInstruction(
   const [whitelistEntry] = getWhitelistEntryPDA(creator);
   const [campaign] = getCampaignPDA(propertyId, creator);
   const [escrowVault] = getEscrowVaultPDA(campaign);
+  const [bondVault] = getBondVaultPDA(campaign);
+  const [propertyRegistry] = await getPropertyRegistryPDA(propertyId);
 
   // create_campaign: SHA256("global:create_campaign")[0..8]
   const discriminator = Buffer.from([
@@ -256,6 +309,8 @@ export async function createCampaignInstruction(
     { pubkey: whitelistEntry, isSigner: false, isWritable: false },
     { pubkey: campaign, isSigner: false, isWritable: true },
     { pubkey: escrowVault, isSigner: false, isWritable: true },
+    { pubkey: bondVault, isSigner: false, isWritable: true },
+    { pubkey: propertyRegistry, isSigner: false, isWritable: true },
     { pubkey: propertyMint, isSigner: false, isWritable: true },
     { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
     { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
@@ -271,12 +326,39 @@ export async function createCampaignInstruction(
   return { transaction, campaign };
 }
 
+/** Size of an `InvestorRecord` in the current layout. */
+const INVESTOR_RECORD_ACCOUNT_SIZE = 8 + 32 + 32 + 8 + 8 + 8 + 1 + 1 + 1 + 1 + 33 + 32;
+
+function resizeInvestorRecordInstruction(
+  payer: PublicKey,
+  campaign: PublicKey,
+  investorRecord: PublicKey
+): TransactionInstruction {
+  // resize_investor_record: SHA256("global:resize_investor_record")[0..8]
+  const discriminator = Buffer.from([
+    0xd1, 0x7a, 0xb5, 0x9b, 0xe4, 0x19, 0x00, 0x68,
+  ]);
+
+  return new TransactionInstruction({
+    keys: [
+      { pubkey: payer, isSigner: true, isWritable: true },
+      { pubkey: campaign, isSigner: false, isWritable: false },
+      { pubkey: investorRecord, isSigner: false, isWritable: true },
+      { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+    ],
+    programId: CROWDFUNDING_PROGRAM_ID,
+    data: discriminator,
+  });
+}
+
 export async function investInstruction(
   investor: PublicKey,
   campaign: PublicKey,
   propertyId: string,
   creator: PublicKey,
-  amountLamports: number
+  amountLamports: number,
+  /** Wallet of the registered affiliate who referred this investment */
+  referrer?: PublicKey
 ): Promise<Transaction> {
   const [escrowVault] = getEscrowVaultPDA(campaign);
   const [investorRecord] = getInvestorRecordPDA(campaign, investor);
@@ -298,18 +380,58 @@ export async function investInstruction(
     { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
   ];
 
-  return new Transaction().add({
+  // Records created before referral attribution are grown first
+  const transaction = new Transaction();
+  const recordInfo = await connection.getAccountInfo(investorRecord);
+  if (recordInfo && recordInfo.data.length < INVESTOR_RECORD_ACCOUNT_SIZE) {
+    transaction.add(resizeInvestorRecordInstruction(investor, campaign, investorRecord));
+  }
+
+  // Optional trailing accounts: the affiliate and its per-campaign referral record
+  if (referrer) {
+    keys.push(
+      { pubkey: getAffiliatePDA(referrer)[0], isSigner: false, isWritable: true },
+      {
+        pubkey: getReferralRecordPDA(campaign, referrer)[0],
+        isSigner: false,
+        isWritable: true,
+      }
+    );
+  }
+
+  return transaction.add({
     keys,
     programId: CROWDFUNDING_PROGRAM_ID,
     data,
   });
 }
 
+/**
+ * Registry entry to pass for a campaign: its PDA, or the program ID (Anchor's
+ * "none" placeholder) for campaigns created before the property registry existed.
+ */
+async function propertyRegistryAccount(
+  propertyId: string,
+  campaign: PublicKey
+): Promise<PublicKey> {
+  const [propertyRegistry] = await getPropertyRegistryPDA(propertyId);
+  const info = await connection.getAccountInfo(propertyRegistry);
+  if (!info) return CROWDFUNDING_PROGRAM_ID;
+  // Layout: discriminator, property_id (u32 len + bytes), campaign
+  const campaignOffset = 8 + 4 + info.data.readUInt32LE(8);
+  const registered = new PublicKey(
+    info.data.slice(campaignOffset, campaignOffset + 32)
+  );
+  return registered.equals(campaign) ? propertyRegistry : CROWDFUNDING_PROGRAM_ID;
+}
+
 export async function cancelCampaignInstruction(
   creator: PublicKey,
   campaign: PublicKey,
   propertyId: string
 ): Promise<Transaction> {
+  const propertyRegistry = await propertyRegistryAccount(propertyId, campaign);
+
   // cancel_campaign: SHA256("global:cancel_campaign")[0..8]
   const discriminator = Buffer.from([
     0x42, 0x0a, 0x20, 0x8a, 0x7a, 0x24, 0x86, 0xca,
@@ -318,6 +440,11 @@ export async function cancelCampaignInstruction(
   const keys = [
     { pubkey: creator, isSigner: true, isWritable: true },
     { pubkey: campaign, isSigner: false, isWritable: true },
+    {
+      pubkey: propertyRegistry,
+      isSigner: false,
+      isWritable: !propertyRegistry.equals(CROWDFUNDING_PROGRAM_ID),
+    },
   ];
 
   return new Transaction().add({
@@ -330,29 +457,61 @@ export async function cancelCampaignInstruction(
 export async function finalizeCampaignInstruction(
   creator: PublicKey,
   campaign: PublicKey,
-  platformWallet: PublicKey
+  platformWallet: PublicKey,
+  propertyId: string,
+  propertyMint: PublicKey,
+  distributionFrequencyDays: number,
+  /** Authority for the dividend pool; defaults to the creator */
+  propertyManager?: PublicKey
 ): Promise<Transaction> {
   const [platformConfig] = getPlatformConfigPDA();
   const [escrowVault] = getEscrowVaultPDA(campaign);
+  const propertyRegistry = await propertyRegistryAccount(propertyId, campaign);
+  const [dividendPool] = getDividendPoolPDA(propertyMint);
+  const [dividendVault] = getDividendVaultPDA(dividendPool);
+  const [taxVault] = getTaxVaultPDA(dividendPool);
+  const [reserveVault] = getReserveVaultPDA(dividendPool);
 
   // sha256("global:finalize_campaign")[0..8]
   const discriminator = Buffer.from([
     0xf1, 0x4c, 0xc9, 0xdd, 0x21, 0xde, 0xdc, 0x8a,
   ]);
 
+  // Args: distribution_frequency_days (u64), property_manager (Option<Pubkey>)
+  const data = Buffer.concat([
+    discriminator,
+    u64LE(distributionFrequencyDays),
+    propertyManager
+      ? Buffer.concat([Buffer.from([1]), propertyManager.toBuffer()])
+      : Buffer.from([0]),
+  ]);
+
   const keys = [
     { pubkey: creator, isSigner: true, isWritable: true },
     { pubkey: platformConfig, isSigner: false, isWritable: false },
     { pubkey: campaign, isSigner: false, isWritable: true },
     { pubkey: escrowVault, isSigner: false, isWritable: true },
     { pubkey: platformWallet, isSigner: false, isWritable: true },
+    {
+      pubkey: propertyRegistry,
+      isSigner: false,
+      isWritable: !propertyRegistry.equals(CROWDFUNDING_PROGRAM_ID),
+    },
+    { pubkey: propertyMint, isSigner: false, isWritable: false },
+    { pubkey: dividendPool, isSigner: false, isWritable: true },
+    { pubkey: dividendVault, isSigner: false, isWritable: true },
+    { pubkey: taxVault, isSigner: false, isWritable: true },
+    { pubkey: reserveVault, isSigner: false, isWritable: true },
+    { pubkey: DIVIDEND_PROGRAM_ID, isSigner: false, isWritable: false },
     { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+    { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+    { pubkey: new PublicKey("SysvarRent111111111111111111111111111111111"), isSigner: false, isWritable: false },
   ];
 
   return new Transaction().add({
     keys,
     programId: CROWDFUNDING_PROGRAM_ID,
-    data: discriminator,
+    data,
   });
 }
 
@@ -497,7 +656,8 @@ export async function invest(
   amountSol: number,
   campaignOverride?: PublicKey,
   /** Exact lamports to transfer; when set, overrides `amountSol` (avoids float floor below on-chain token_price). */
-  lamportsOverride?: number
+  lamportsOverride?: number,
+  referrer?: PublicKey
 ): Promise<string> {
   const campaign = campaignOverride ?? getCampaignPDA(propertyId, creator)[0];
   const amountLamports =
@@ -510,7 +670,8 @@ export async function invest(
     campaign,
     propertyId,
     creator,
-    amountLamports
+    amountLamports,
+    referrer
   );
 
   const { blockhash } = await connection.getLatestBlockhash();
@@ -552,7 +713,9 @@ export async function cancelCampaign(
 export async function finalizeCampaign(
   wallet: WalletAdapter,
   propertyId: string,
-  campaignOverride?: PublicKey
+  campaignOverride?: PublicKey,
+  distributionFrequencyDays = 30,
+  propertyManager?: PublicKey
 ): Promise<string> {
   const campaign =
     campaignOverride ?? getCampaignPDA(propertyId, wallet.publicKey)[0];
@@ -560,11 +723,19 @@ export async function finalizeCampaign(
   if (!platformWallet) {
     throw new Error("Platform config not initialized");
   }
+  const campaignData = await fetchCampaign(propertyId, wallet.publicKey, campaign);
+  if (!campaignData) {
+    throw new Error("Campaign not found");
+  }
 
   const transaction = await finalizeCampaignInstruction(
     wallet.publicKey,
     campaign,
-    platformWallet
+    platformWallet,
+    propertyId,
+    campaignData.propertyMint,
+    distributionFrequencyDays,
+    propertyManager
   );
 
   const { blockhash } = await connection.getLatestBlockhash();
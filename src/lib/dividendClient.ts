@@ -7,7 +7,7 @@ import {
 } from "@solana/web3.js";
 import { TOKEN_PROGRAM_ID, getAssociatedTokenAddress } from "@solana/spl-token";
 import { connection, SOLANA_NETWORK } from "./solana";
-import { u64LE } from "./binaryUtils";
+import { readU128LE, u64LE } from "./binaryUtils";
 
 // Program ID - Update this after deploying the contract
 export const DIVIDEND_PROGRAM_ID = new PublicKey(
@@ -28,6 +28,10 @@ export interface DividendPool {
   distributionFrequencyDays: number;
   lastDistributionTime: number;
   totalDepositedCurrentEpoch: number;
+  /** Receives the management fee; absent on pools in the pre-upgrade layout. */
+  manager?: PublicKey;
+  /** Whether distributions must pass the pool's `Waterfall`. */
+  hasWaterfall?: boolean;
 }
 
 export interface DistributionRecord {
@@ -35,7 +39,8 @@ export interface DistributionRecord {
   epoch: number;
   totalAmount: number;
   totalTokenSupply: number;
-  amountPerToken: number;
+  /** Lamports per token, scaled by `AMOUNT_PER_TOKEN_SCALE`. */
+  amountPerToken: bigint;
   distributedAt: number;
   totalClaimed: number;
 }
@@ -50,11 +55,30 @@ export interface ClaimRecord {
   claimed: boolean;
 }
 
+/** A holder's balance in a distribution's snapshot and its merkle proof. */
+export interface SnapshotClaim {
+  snapshotBalance: bigint | number;
+  /** 32-byte sibling hashes, leaf to root. */
+  proof: Buffer[];
+}
+
+/** Balance snapshot a distribution is started with. */
+export interface DistributionSnapshot {
+  slot: bigint | number;
+  /** 32-byte merkle root of `balance_leaf(holder, balance)` leaves. */
+  root: Buffer;
+  /** Sum of the snapshot's balances. */
+  total: bigint | number;
+}
+
 export interface WalletAdapter {
   publicKey: PublicKey;
   signTransaction: (tx: Transaction) => Promise<Transaction>;
 }
 
+/** Fixed-point scale of `DistributionRecord.amount_per_token`. */
+export const AMOUNT_PER_TOKEN_SCALE = BigInt("1000000000000000000");
+
 // ============================================================================
 // PDA Derivation Functions
 // ============================================================================
@@ -73,6 +97,57 @@ export function getDividendVaultPDA(dividendPool: PublicKey): [PublicKey, number
   );
 }
 
+export function getTaxVaultPDA(dividendPool: PublicKey): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("tax_vault"), dividendPool.toBuffer()],
+    DIVIDEND_PROGRAM_ID
+  );
+}
+
+export function getReserveVaultPDA(dividendPool: PublicKey): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("reserve_vault"), dividendPool.toBuffer()],
+    DIVIDEND_PROGRAM_ID
+  );
+}
+
+export function getHolderCursorPDA(
+  dividendPool: PublicKey,
+  holder: PublicKey
+): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("cursor"), dividendPool.toBuffer(), holder.toBuffer()],
+    DIVIDEND_PROGRAM_ID
+  );
+}
+
+export function getTaxProfilePDA(
+  dividendPool: PublicKey,
+  holder: PublicKey
+): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("tax_profile"), dividendPool.toBuffer(), holder.toBuffer()],
+    DIVIDEND_PROGRAM_ID
+  );
+}
+
+export function getPayoutPreferencePDA(
+  dividendPool: PublicKey,
+  holder: PublicKey
+): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("payout"), dividendPool.toBuffer(), holder.toBuffer()],
+    DIVIDEND_PROGRAM_ID
+  );
+}
+
+export function getWaterfallPDA(dividendPool: PublicKey): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("waterfall"), dividendPool.toBuffer()],
+    DIVIDEND_PROGRAM_ID
+  );
+}
+
 export function getDistributionRecordPDA(
   dividendPool: PublicKey,
   epoch: number
@@ -105,6 +180,8 @@ export async function initializePoolInstruction(
 ): Promise<{ instruction: Transaction; dividendPool: PublicKey }> {
   const [dividendPool] = getDividendPoolPDA(propertyMint);
   const [dividendVault] = getDividendVaultPDA(dividendPool);
+  const [taxVault] = getTaxVaultPDA(dividendPool);
+  const [reserveVault] = getReserveVaultPDA(dividendPool);
 
   // Anchor instruction discriminator for "initialize_pool"
   // initialize_pool: SHA256("global:initialize_pool")[0..8]
@@ -130,6 +207,14 @@ export async function initializePoolInstruction(
     { pubkey: propertyMint, isSigner: false, isWritable: false },
     { pubkey: dividendPool, isSigner: false, isWritable: true },
     { pubkey: dividendVault, isSigner: false, isWritable: true },
+    { pubkey: taxVault, isSigner: false, isWritable: true },
+    { pubkey: reserveVault, isSigner: false, isWritable: true },
+    // SOL pool signed by the mint authority: payout_mint, token_vault, campaign
+    // and platform_config are omitted (the program ID stands in for each)
+    { pubkey: DIVIDEND_PROGRAM_ID, isSigner: false, isWritable: false },
+    { pubkey: DIVIDEND_PROGRAM_ID, isSigner: false, isWritable: false },
+    { pubkey: DIVIDEND_PROGRAM_ID, isSigner: false, isWritable: false },
+    { pubkey: DIVIDEND_PROGRAM_ID, isSigner: false, isWritable: false },
     { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
     { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
     { pubkey: new PublicKey("SysvarRent111111111111111111111111111111111"), isSigner: false, isWritable: false },
@@ -179,10 +264,23 @@ export async function depositDividendInstruction(
 export async function startDistributionInstruction(
   authority: PublicKey,
   propertyMint: PublicKey,
-  currentEpoch: number
+  currentEpoch: number,
+  snapshot: DistributionSnapshot,
+  overrideInterval = false
 ): Promise<Transaction> {
   const [dividendPool] = getDividendPoolPDA(propertyMint);
   const [distributionRecord] = getDistributionRecordPDA(dividendPool, currentEpoch);
+  const [dividendVault] = getDividendVaultPDA(dividendPool);
+  const [reserveVault] = getReserveVaultPDA(dividendPool);
+  const [waterfall] = getWaterfallPDA(dividendPool);
+
+  const pool = await fetchDividendPoolByAddress(dividendPool);
+  if (!pool?.manager) {
+    throw new Error("Dividend pool not found or not migrated");
+  }
+  if (snapshot.root.length !== 32) {
+    throw new Error("Snapshot root must be 32 bytes");
+  }
 
   // Anchor instruction discriminator for "start_distribution"
   // start_distribution: SHA256("global:start_distribution")[0..8]
@@ -190,31 +288,68 @@ export async function startDistributionInstruction(
     0x76, 0xe6, 0xd7, 0x4b, 0x53, 0x02, 0xa3, 0x23,
   ]);
 
+  // start_distribution(snapshot_slot: u64, snapshot_root: [u8; 32],
+  // snapshot_total: u64, override_interval: bool)
+  const data = Buffer.concat([
+    discriminator,
+    u64LE(snapshot.slot),
+    snapshot.root,
+    u64LE(snapshot.total),
+    Buffer.from([overrideInterval ? 1 : 0]),
+  ]);
+
   const keys = [
     { pubkey: authority, isSigner: true, isWritable: true },
     { pubkey: dividendPool, isSigner: false, isWritable: true },
     { pubkey: propertyMint, isSigner: false, isWritable: false },
     { pubkey: distributionRecord, isSigner: false, isWritable: true },
+    { pubkey: dividendVault, isSigner: false, isWritable: true },
+    { pubkey: pool.manager, isSigner: false, isWritable: true },
+    { pubkey: reserveVault, isSigner: false, isWritable: true },
+    // SOL pool: token_vault, manager_token_account and reserve_token_vault are
+    // omitted (the program ID stands in for each)
+    { pubkey: DIVIDEND_PROGRAM_ID, isSigner: false, isWritable: false },
+    { pubkey: DIVIDEND_PROGRAM_ID, isSigner: false, isWritable: false },
+    { pubkey: DIVIDEND_PROGRAM_ID, isSigner: false, isWritable: false },
+    pool.hasWaterfall
+      ? { pubkey: waterfall, isSigner: false, isWritable: true }
+      : { pubkey: DIVIDEND_PROGRAM_ID, isSigner: false, isWritable: false },
+    // token_program omitted
+    { pubkey: DIVIDEND_PROGRAM_ID, isSigner: false, isWritable: false },
     { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
   ];
 
   return new Transaction().add({
     keys,
     programId: DIVIDEND_PROGRAM_ID,
-    data: discriminator,
+    data,
   });
 }
 
 export async function claimDividendInstruction(
   user: PublicKey,
   propertyMint: PublicKey,
-  epoch: number
+  epoch: number,
+  claim: SnapshotClaim
 ): Promise<Transaction> {
   const [dividendPool] = getDividendPoolPDA(propertyMint);
   const [dividendVault] = getDividendVaultPDA(dividendPool);
   const [distributionRecord] = getDistributionRecordPDA(dividendPool, epoch);
   const [claimRecord] = getClaimRecordPDA(distributionRecord, user);
-  const userTokenAccount = await getAssociatedTokenAddress(propertyMint, user);
+  const [holderCursor] = getHolderCursorPDA(dividendPool, user);
+  const [taxProfile] = getTaxProfilePDA(dividendPool, user);
+  const [taxVault] = getTaxVaultPDA(dividendPool);
+  const [payoutPreference] = getPayoutPreferencePDA(dividendPool, user);
+
+  // A holder who set a payout preference is paid at its payout address
+  const preferenceInfo = await connection.getAccountInfo(payoutPreference);
+  const payoutAddress = preferenceInfo
+    ? new PublicKey(preferenceInfo.data.slice(8 + 64, 8 + 96))
+    : null;
+
+  if (claim.proof.some((node) => node.length !== 32)) {
+    throw new Error("Snapshot proof nodes must be 32 bytes");
+  }
 
   // Anchor instruction discriminator for "claim_dividend"
   // claim_dividend: SHA256("global:claim_dividend")[0..8]
@@ -222,17 +357,43 @@ export async function claimDividendInstruction(
     0x0f, 0x1d, 0xcf, 0x78, 0x99, 0xb2, 0xa4, 0x5b,
   ]);
 
-  const epochBuffer = u64LE(epoch);
+  // claim_dividend(epoch: u64, snapshot_balance: u64, proof: Vec<[u8; 32]>)
+  const proofLenBuffer = Buffer.alloc(4);
+  proofLenBuffer.writeUInt32LE(claim.proof.length);
 
-  const data = Buffer.concat([discriminator, epochBuffer]);
+  const data = Buffer.concat([
+    discriminator,
+    u64LE(epoch),
+    u64LE(claim.snapshotBalance),
+    proofLenBuffer,
+    ...claim.proof,
+  ]);
 
   const keys = [
     { pubkey: user, isSigner: true, isWritable: true },
+    // holder: the signer claims their own snapshot balance
+    { pubkey: user, isSigner: false, isWritable: false },
+    // redirect omitted
+    { pubkey: DIVIDEND_PROGRAM_ID, isSigner: false, isWritable: false },
+    payoutAddress
+      ? { pubkey: payoutPreference, isSigner: false, isWritable: false }
+      : { pubkey: DIVIDEND_PROGRAM_ID, isSigner: false, isWritable: false },
+    payoutAddress
+      ? { pubkey: payoutAddress, isSigner: false, isWritable: true }
+      : { pubkey: DIVIDEND_PROGRAM_ID, isSigner: false, isWritable: false },
     { pubkey: dividendPool, isSigner: false, isWritable: false },
     { pubkey: distributionRecord, isSigner: false, isWritable: true },
     { pubkey: dividendVault, isSigner: false, isWritable: true },
-    { pubkey: userTokenAccount, isSigner: false, isWritable: false },
     { pubkey: claimRecord, isSigner: false, isWritable: true },
+    { pubkey: holderCursor, isSigner: false, isWritable: true },
+    // SOL pool: token_vault and recipient_token_account omitted
+    { pubkey: DIVIDEND_PROGRAM_ID, isSigner: false, isWritable: false },
+    { pubkey: DIVIDEND_PROGRAM_ID, isSigner: false, isWritable: false },
+    { pubkey: taxProfile, isSigner: false, isWritable: false },
+    { pubkey: taxVault, isSigner: false, isWritable: true },
+    // tax_token_vault and token_program omitted
+    { pubkey: DIVIDEND_PROGRAM_ID, isSigner: false, isWritable: false },
+    { pubkey: DIVIDEND_PROGRAM_ID, isSigner: false, isWritable: false },
     { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
   ];
 
@@ -244,7 +405,8 @@ export async function claimDividendInstruction(
 }
 
 /** Anchor account size for `ClaimRecord` (discriminator + body + padding). */
-const CLAIM_RECORD_ANCHOR_ACCOUNT_SIZE = 8 + 32 + 32 + 8 + 8 + 8 + 1 + 1 + 32;
+const CLAIM_RECORD_ANCHOR_ACCOUNT_SIZE =
+  8 + 32 + 32 + 8 + 8 + 8 + 1 + 1 + 8 + 8 + 8 + 8 + 32;
 
 /** In full account data: 8-byte disc + 32-byte user; distribution pubkey starts here. */
 const CLAIM_ACCOUNT_MEMCMP_DISTRIBUTION_OFFSET = 40;
@@ -287,56 +449,6 @@ export async function sumClaimedLamportsForDistribution(
   }
 }
 
-export async function setDistributionTotalClaimedInstruction(
-  authority: PublicKey,
-  propertyMint: PublicKey,
-  epoch: number,
-  newTotalClaimedLamports: bigint
-): Promise<Transaction> {
-  const [dividendPool] = getDividendPoolPDA(propertyMint);
-  const [distributionRecord] = getDistributionRecordPDA(dividendPool, epoch);
-
-  const discriminator = Buffer.from([
-    0x60, 0x93, 0xaf, 0x84, 0xc7, 0x50, 0x4f, 0xac,
-  ]);
-  const epochBuf = u64LE(epoch);
-  const totalBuf = u64LE(newTotalClaimedLamports);
-  const data = Buffer.concat([discriminator, epochBuf, totalBuf]);
-
-  const keys = [
-    { pubkey: authority, isSigner: true, isWritable: true },
-    { pubkey: dividendPool, isSigner: false, isWritable: false },
-    { pubkey: distributionRecord, isSigner: false, isWritable: true },
-  ];
-
-  return new Transaction().add({
-    keys,
-    programId: DIVIDEND_PROGRAM_ID,
-    data,
-  });
-}
-
-export async function setDistributionTotalClaimed(
-  wallet: WalletAdapter,
-  propertyMint: PublicKey,
-  epoch: number,
-  newTotalClaimedLamports: bigint
-): Promise<string> {
-  const transaction = await setDistributionTotalClaimedInstruction(
-    wallet.publicKey,
-    propertyMint,
-    epoch,
-    newTotalClaimedLamports
-  );
-  const { blockhash } = await connection.getLatestBlockhash();
-  transaction.recentBlockhash = blockhash;
-  transaction.feePayer = wallet.publicKey;
-  const signedTx = await wallet.signTransaction(transaction);
-  const signature = await connection.sendRawTransaction(signedTx.serialize());
-  await connection.confirmTransaction(signature, "confirmed");
-  return signature;
-}
-
 // ============================================================================
 // High-Level Functions
 // ============================================================================
@@ -391,12 +503,16 @@ export async function depositDividend(
 export async function startDistribution(
   wallet: WalletAdapter,
   propertyMint: PublicKey,
-  currentEpoch: number
+  currentEpoch: number,
+  snapshot: DistributionSnapshot,
+  overrideInterval = false
 ): Promise<string> {
   const transaction = await startDistributionInstruction(
     wallet.publicKey,
     propertyMint,
-    currentEpoch
+    currentEpoch,
+    snapshot,
+    overrideInterval
   );
 
   const { blockhash } = await connection.getLatestBlockhash();
@@ -413,12 +529,14 @@ export async function startDistribution(
 export async function claimDividend(
   wallet: WalletAdapter,
   propertyMint: PublicKey,
-  epoch: number
+  epoch: number,
+  claim: SnapshotClaim
 ): Promise<string> {
   const transaction = await claimDividendInstruction(
     wallet.publicKey,
     propertyMint,
-    epoch
+    epoch,
+    claim
   );
 
   const { blockhash } = await connection.getLatestBlockhash();
@@ -462,7 +580,21 @@ export function decodeDividendPoolAccountData(data: Buffer): DividendPool | null
     o += 8;
     const totalDepositedCurrentEpoch = Number(data.readBigUInt64LE(o));
     o += 8;
-    // bump u8 — remainder is padding
+    // bump u8, excluded_account_count u32, then payout_mint Option<Pubkey>
+    o += 1 + 4;
+    let manager: PublicKey | undefined;
+    let hasWaterfall: boolean | undefined;
+    if (o + 1 <= data.length) {
+      o += data.readUInt8(o) === 1 ? 33 : 1;
+      // claim_period_days, unclaimed_policy, treasury, streaming,
+      // compliance_authority, management_fee_bps, reserve_bps
+      o += 8 + 1 + 32 + 1 + 32 + 2 + 2;
+      if (o + 32 + 8 + 8 + 1 <= data.length) {
+        manager = new PublicKey(data.slice(o, o + 32));
+        o += 32 + 8 + 8;
+        hasWaterfall = data.readUInt8(o) !== 0;
+      }
+    }
     return {
       authority,
       propertyMint,
@@ -473,6 +605,8 @@ export function decodeDividendPoolAccountData(data: Buffer): DividendPool | null
       distributionFrequencyDays,
       lastDistributionTime,
       totalDepositedCurrentEpoch,
+      manager,
+      hasWaterfall,
     };
   } catch {
     return null;
@@ -542,15 +676,15 @@ export async function fetchClaimRecord(
 
 function decodeDistributionRecordData(data: Buffer): DistributionRecord | null {
   try {
-    if (data.length < 81) return null;
+    if (data.length < 89) return null;
     return {
       pool: new PublicKey(data.slice(0, 32)),
       epoch: Number(data.readBigUInt64LE(32)),
       totalAmount: Number(data.readBigUInt64LE(40)),
       totalTokenSupply: Number(data.readBigUInt64LE(48)),
-      amountPerToken: Number(data.readBigUInt64LE(56)),
-      distributedAt: Number(data.readBigInt64LE(64)),
-      totalClaimed: Number(data.readBigUInt64LE(72)),
+      amountPerToken: readU128LE(data, 56),
+      distributedAt: Number(data.readBigInt64LE(72)),
+      totalClaimed: Number(data.readBigUInt64LE(80)),
     };
   } catch {
     return null;
@@ -739,7 +873,8 @@ export async function getClaimableAmount(
     paidSoFarLamports > totalLamports ? totalLamports : paidSoFarLamports;
   const remainingLamports = totalLamports - cappedPaidLamports;
 
-  const rawLamports = BigInt(tokenBalance) * BigInt(distribution.amountPerToken);
+  const rawLamports =
+    (BigInt(tokenBalance) * distribution.amountPerToken) / AMOUNT_PER_TOKEN_SCALE;
   const payableLamports =
     rawLamports < remainingLamports ? rawLamports : remainingLamports;
 
@@ -31,7 +31,6 @@ import {
   getClaimableAmount,
   hasClaimedDividend,
   getDividendPoolPDA,
-  setDistributionTotalClaimed,
   sumClaimedLamportsForDistribution,
 } from "./dividendClient";
 import {
@@ -59,7 +58,6 @@ export {
   getClaimableAmount,
   hasClaimedDividend,
   getDividendPoolPDA,
-  setDistributionTotalClaimed,
   sumClaimedLamportsForDistribution,
   hasAnyInvestorClaimedTokens,
 };
@@ -302,10 +300,10 @@ export async function transferMintAuthority(
 // Full crowdfunding-based tokenization:
 //   1. Create the property token mint (wallet = mint authority initially)
 //   2. Initialize a dividend pool for it
-//   3. Create a crowdfunding campaign (wallet = creator)
-//   4. Transfer mint authority to the campaign PDA so the program can
-//      mint tokens to investors via `claim_tokens` after the campaign is
-//      finalized.
+//   3. Transfer mint authority to the (not yet created) campaign PDA so the
+//      program can mint tokens to investors via `claim_tokens` after the
+//      campaign is finalized. `create_campaign` requires this up front.
+//   4. Create a crowdfunding campaign (wallet = creator)
 //
 // No tokens are minted to the owner up-front. The owner receives SOL when
 // they finalize the campaign; investors receive tokens when they claim.
@@ -359,7 +357,12 @@ export async function tokenizePropertyForCrowdfunding(
     distributionFrequencyDays
   );
 
-  // 3. Create the crowdfunding campaign
+  // 3. Hand mint authority over to the campaign PDA so the program can
+  //    mint tokens for investors when they claim.
+  const [campaignPda] = getCampaignPDA(params.propertyId, wallet.publicKey);
+  const authoritySignature = await transferMintAuthority(wallet, mintAddress, campaignPda);
+
+  // 4. Create the crowdfunding campaign
   const { signature: campaignSignature, campaign } = await createCampaign(
     wallet,
     mintAddress,
@@ -371,10 +374,6 @@ export async function tokenizePropertyForCrowdfunding(
     params.totalTokens
   );
 
-  // 4. Hand mint authority over to the campaign PDA so the program can
-  //    mint tokens for investors when they claim.
-  const authoritySignature = await transferMintAuthority(wallet, mintAddress, campaign);
-
   return {
     mintAddress: mintAddress.toBase58(),
     pricePerToken: pricePerTokenUsd,